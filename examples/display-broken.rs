@@ -1,8 +1,10 @@
 use std::error::Error;
 
 use clap::{Parser, Subcommand};
-use elstatus::{display, email, Equipment};
-use tera::Tera;
+use elstatus::{
+    data_dir::DataDir, display, email, matrix, notifier::Notifier, run::RunContext,
+    summary::StatusSummary, webhook, Equipment,
+};
 
 static EQUIPMENT_JSON: &str = include_str!("elstatus.broken.json");
 
@@ -19,6 +21,12 @@ enum Command {
     EMail(email::EMailArgs),
     /// Update the epaper displays using OpenEPaperLink
     Display(display::DisplayArgs),
+    /// Render the epaper images to disk without uploading them
+    RenderOnly(display::RenderArgs),
+    /// Send the status to a Matrix room
+    Matrix(matrix::MatrixArgs),
+    /// POST the status as JSON to a URL
+    Webhook(webhook::WebhookArgs),
 }
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
@@ -26,24 +34,50 @@ fn main() -> Result<(), Box<dyn Error>> {
     let equipments: Vec<Equipment> = serde_json::from_str(EQUIPMENT_JSON)?;
 
     for equipment in equipments.iter() {
-        println!("{:?}", equipment);
+        println!("{}", equipment);
     }
 
-    match cli.command {
-        Command::EMail(email_args) => {
-            let mut tera = Tera::default();
-            tera.add_raw_templates(vec![
-                ("status.html", include_str!("../src/templates/status.html")),
-                ("status.txt", include_str!("../src/templates/status.txt")),
-                ("errors.txt", include_str!("../src/templates/errors.txt")),
-            ])?;
-
-            email::send_result(&equipments, &vec![], &tera, &email_args);
-
-            email::send_errors(&vec![], &tera, &email_args);
-            Ok(())
+    let data_dir = DataDir::default();
+    let previous = display::load_state(&data_dir);
+    let run = RunContext::new();
+    let updated = display::UpdatedImages::default();
+    let renderer = match &cli.command {
+        Command::Display(display_args) => display_args.renderer(),
+        Command::RenderOnly(render_args) => render_args.renderer(),
+        _ => display::DisplayRenderer::new(),
+    };
+    let notifier: Box<dyn Notifier> = match &cli.command {
+        Command::EMail(email_args) => Box::new(email::EMailNotifier {
+            args: email_args,
+            max_listed: None,
+            displayed: None,
+            renderer: &renderer,
+            run: &run,
+            data_dir: &data_dir,
+        }),
+        Command::Display(display_args) => Box::new(display::DisplayNotifier {
+            args: display_args,
+            previous: previous.as_deref(),
+            updated: &updated,
+            renderer: &renderer,
+            run: &run,
+            data_dir: &data_dir,
+        }),
+        Command::RenderOnly(render_args) => {
+            return display::render(
+                &equipments,
+                &renderer,
+                &render_args.image_encoding,
+                &run,
+                &data_dir,
+            )
         }
-        Command::Display(display_args) => display::update(&equipments, &display_args),
-    }
+        Command::Matrix(matrix_args) => Box::new(matrix::MatrixNotifier {
+            args: matrix_args,
+            max_listed: None,
+        }),
+        Command::Webhook(webhook_args) => Box::new(webhook_args),
+    };
+    let summary = StatusSummary::new(&equipments, &[]);
+    notifier.notify(&equipments, &[], &summary)
 }
-
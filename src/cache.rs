@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The directory API responses are cached in by default.
+pub const CACHE_DIR: &str = "/tmp/elstatus.cache";
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// Seconds since the UNIX epoch at which the response was fetched.
+    fetched_at: u64,
+    response: String,
+}
+
+/// An on-disk cache of raw accessibility-cloud responses,
+/// keyed by the API instance and the query of the request.
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    fn path(&self, base_url: &str, query: &[(&str, String)]) -> PathBuf {
+        // Each API instance gets its own directory, so switching instances doesn't serve the other's responses
+        let instance = blake3::hash(base_url.as_bytes()).to_hex();
+        // Hashing the whole query keeps different queries apart and the key inside the cache directory
+        let key = serde_json::to_string(query).unwrap_or_default();
+        let key = blake3::hash(key.as_bytes()).to_hex();
+        self.dir.join(&instance[..16]).join(format!("{key}.json"))
+    }
+
+    /// Returns the cached response, unless it is missing or older than the TTL.
+    pub fn get(&self, base_url: &str, query: &[(&str, String)]) -> Option<String> {
+        let entry = std::fs::read_to_string(self.path(base_url, query)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&entry).ok()?;
+        let age = now().checked_sub(entry.fetched_at)?;
+        (age < self.ttl.as_secs()).then_some(entry.response)
+    }
+
+    pub fn store(
+        &self,
+        base_url: &str,
+        query: &[(&str, String)],
+        response: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = self.path(base_url, query);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let entry = CacheEntry {
+            fetched_at: now(),
+            response: response.to_owned(),
+        };
+        std::fs::write(path, serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
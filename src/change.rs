@@ -0,0 +1,42 @@
+use crate::Equipment;
+
+/// How the status of an elevator changed since the previous run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusChange {
+    /// It worked before and is broken now
+    NewlyBroken,
+    /// It was broken before and works again
+    BackInService,
+}
+
+/// The working status as far as alerts are concerned.
+fn reported_working(equipment: &Equipment) -> Option<bool> {
+    match equipment.working {
+        Some(false) if equipment.recently_broken => Some(true),
+        working => working,
+    }
+}
+
+/// The elevators whose status changed between `previous` and `equipments`, in the order of `equipments`.
+/// Elevators are the same if they have the same name and location.
+/// Changes from or to an unknown status don't count, nor do elevators that weren't there before.
+/// Recently broken elevators count as still working, see [`Equipment::recently_broken`],
+/// so they are only reported as newly broken once they have been broken for long enough.
+pub fn status_changes<'a>(
+    previous: &[Equipment],
+    equipments: &'a [Equipment],
+) -> Vec<(StatusChange, &'a Equipment)> {
+    equipments
+        .iter()
+        .filter_map(|equipment| {
+            let before = previous.iter().find(|before| {
+                before.name == equipment.name && before.location() == equipment.location()
+            })?;
+            match (reported_working(before), reported_working(equipment)) {
+                (Some(true), Some(false)) => Some((StatusChange::NewlyBroken, equipment)),
+                (Some(false), Some(true)) => Some((StatusChange::BackInService, equipment)),
+                _ => None,
+            }
+        })
+        .collect()
+}
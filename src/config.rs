@@ -0,0 +1,99 @@
+use serde_json::Value;
+
+/// A `${NAME}` in a configuration file that couldn't be expanded.
+#[derive(Debug, thiserror::Error)]
+pub enum EnvError {
+    #[error("Environment variable {name} is not set")]
+    Unset { name: String },
+    #[error("Missing \"}}\" after \"${{\" in: {text}")]
+    Unterminated { text: String },
+}
+
+/// Replace every `${NAME}` in `text` with the value of the environment variable `NAME`,
+/// so secrets don't have to be written into configuration files.
+pub fn expand_env(text: &str) -> Result<String, EnvError> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let Some(length) = rest[start..].find('}') else {
+            return Err(EnvError::Unterminated {
+                text: text.to_owned(),
+            });
+        };
+        let name = &rest[start + 2..start + length];
+        let value = std::env::var(name).map_err(|_| EnvError::Unset {
+            name: name.to_owned(),
+        })?;
+        expanded.push_str(&value);
+        rest = &rest[start + length + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Expand the environment variables in every string of `value`, see [`expand_env`].
+/// Object keys are left as they are.
+pub fn expand_env_in(value: &mut Value) -> Result<(), EnvError> {
+    match value {
+        Value::String(text) => *text = expand_env(text)?,
+        Value::Array(values) => values.iter_mut().try_for_each(expand_env_in)?,
+        Value::Object(map) => map.values_mut().try_for_each(expand_env_in)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+/// A backend section of the config file that can't be turned into command line options.
+#[derive(Debug, thiserror::Error)]
+pub enum SectionError {
+    #[error("Section {section} must be an object of options")]
+    NotAnObject { section: String },
+    #[error(
+        "Invalid value for {key}: {value}, expected a string, number, boolean or a list of them"
+    )]
+    InvalidValue { key: String, value: Value },
+}
+
+/// The command line options for a `section` of the config file, e.g.
+/// `{ "smtp_server": "smtp.example.org", "cc": ["a@example.org", "b@example.org"], "attach_image": true }`
+/// becomes `--smtp-server smtp.example.org --cc a@example.org --cc b@example.org --attach-image`.
+/// Options named in `given` are skipped, so the ones on the command line override the file.
+/// Flags that are `false` and values that are `null` are left out.
+pub fn section_args(
+    name: &str,
+    section: &Value,
+    given: &[String],
+) -> Result<Vec<String>, SectionError> {
+    let Value::Object(options) = section else {
+        return Err(SectionError::NotAnObject {
+            section: name.to_owned(),
+        });
+    };
+    let mut args = Vec::new();
+    for (key, value) in options {
+        let option = key.replace('_', "-");
+        if given.contains(&option) {
+            continue;
+        }
+        let values = match value {
+            Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            match value {
+                Value::Bool(true) => args.push(format!("--{option}")),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(text) => args.extend([format!("--{option}"), text.clone()]),
+                Value::Number(number) => args.extend([format!("--{option}"), number.to_string()]),
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(SectionError::InvalidValue {
+                        key: key.clone(),
+                        value: value.clone(),
+                    })
+                }
+            }
+        }
+    }
+    Ok(args)
+}
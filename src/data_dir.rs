@@ -0,0 +1,56 @@
+use crate::cache;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Where the state files are kept without a data directory.
+const DEFAULT_STATE_DIR: &str = "/tmp";
+
+/// The directory all generated files are kept in: the state of previous runs,
+/// cached API responses and rendered images.
+/// Without one, state and cache files go to /tmp and rendered images to the current directory.
+#[derive(Debug, Clone, Default)]
+pub struct DataDir {
+    dir: Option<PathBuf>,
+}
+
+impl DataDir {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    /// Create the directory if needed.
+    pub fn create(&self) -> io::Result<()> {
+        match &self.dir {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }
+    }
+
+    /// The directory itself, if one was given.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// The path of the state file called `name`, e.g. "elstatus.state.json".
+    pub fn state_file(&self, name: &str) -> PathBuf {
+        self.dir
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_STATE_DIR))
+            .join(name)
+    }
+
+    /// Where API responses are cached.
+    pub fn cache_dir(&self) -> PathBuf {
+        match &self.dir {
+            Some(dir) => dir.join("cache"),
+            None => PathBuf::from(cache::CACHE_DIR),
+        }
+    }
+
+    /// Where rendered images are written to.
+    pub fn image_dir(&self) -> &Path {
+        self.dir.as_deref().unwrap_or(Path::new("."))
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{data_dir::DataDir, Equipment};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, error::Error, time::Duration};
+
+/// Where the status history is kept between runs.
+const HISTORY_FILE: &str = "elstatus.history.json";
+
+/// How far back status changes are counted for `--max-changes-per-hour`.
+const FLAPPING_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Args, Debug, Default)]
+pub struct DebounceArgs {
+    /// Only report a new status once it has been stable for this long, e.g. "10m"
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub min_status_age: Option<Duration>,
+
+    /// Keep reporting the last stable status of elevators whose status
+    /// changed more often than this within the last hour
+    #[arg(long)]
+    pub max_changes_per_hour: Option<usize>,
+
+    /// Only alert about elevators that have been broken for at least this long, e.g. "30m".
+    /// Elevators that broke down more recently are listed as recently reported broken.
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub broken_min_duration: Option<Duration>,
+}
+
+impl DebounceArgs {
+    pub fn is_enabled(&self) -> bool {
+        self.min_status_age.is_some()
+            || self.max_changes_per_hour.is_some()
+            || self.broken_min_duration.is_some()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EquipmentHistory {
+    /// The status the API reported last
+    observed: Option<bool>,
+    /// Since when the API reports the `observed` status
+    since: DateTime<Utc>,
+    /// The last status that was stable long enough to be reported
+    reported: Option<bool>,
+    /// When the observed status changed within the last hour
+    changes: Vec<DateTime<Utc>>,
+}
+
+/// The recent status changes of every elevator, used to hold back
+/// changes of flapping sensors until they have settled down.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatusHistory {
+    equipments: HashMap<String, EquipmentHistory>,
+}
+
+/// Elevators are told apart by their id, as several of them at one place can share a name.
+fn key(equipment: &Equipment) -> String {
+    match equipment.id() {
+        Some(id) => id.to_owned(),
+        None => format!(
+            "{}/{}",
+            equipment.location().unwrap_or_default(),
+            equipment.name
+        ),
+    }
+}
+
+impl StatusHistory {
+    /// The history stored by the last run, or an empty one.
+    pub fn load(data_dir: &DataDir) -> Self {
+        std::fs::read_to_string(data_dir.state_file(HISTORY_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn store(&self, data_dir: &DataDir) -> Result<(), Box<dyn Error>> {
+        std::fs::write(
+            data_dir.state_file(HISTORY_FILE),
+            serde_json::to_string(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// Record the status of `equipments` and return them with the status that should be reported.
+    /// A new status is only reported once it is at least `--min-status-age` old and the elevator
+    /// didn't change its status more than `--max-changes-per-hour` times.
+    /// Until then, the last stable status is reported instead.
+    pub fn debounce(
+        &mut self,
+        equipments: &[Equipment],
+        now: DateTime<Utc>,
+        args: &DebounceArgs,
+    ) -> Vec<Equipment> {
+        let mut equipments = equipments.to_vec();
+        let mut seen = HashMap::new();
+
+        for equipment in equipments.iter_mut() {
+            let key = key(equipment);
+            let since = equipment.last_update.map_or(now, |since| since.min(now));
+            let mut history = self
+                .equipments
+                .remove(&key)
+                .unwrap_or_else(|| EquipmentHistory {
+                    observed: equipment.working,
+                    since,
+                    reported: equipment.working,
+                    changes: Vec::new(),
+                });
+
+            if history.observed != equipment.working {
+                history.observed = equipment.working;
+                history.since = since;
+                history.changes.push(now);
+            }
+            history
+                .changes
+                .retain(|change| (now - *change).to_std().unwrap_or_default() < FLAPPING_WINDOW);
+
+            let old_enough = args.min_status_age.is_none_or(|min_age| {
+                (now - history.since).to_std().unwrap_or_default() >= min_age
+            });
+            let flapping = args
+                .max_changes_per_hour
+                .is_some_and(|max_changes| history.changes.len() > max_changes);
+
+            if old_enough && !flapping {
+                history.reported = history.observed;
+            } else if history.reported != history.observed {
+                println!(
+                    "🔀 Ignoring unstable status of {}, still reporting {:?}",
+                    equipment.name, history.reported
+                );
+            }
+            equipment.working = history.reported;
+
+            seen.insert(key, history);
+        }
+
+        // Forget about elevators that are no longer monitored
+        self.equipments = seen;
+        equipments
+    }
+
+    /// The broken `equipments` that have been broken for less than `--broken-min-duration`,
+    /// according to the status recorded by the last call to [`Self::debounce`].
+    pub fn recently_broken<'a>(
+        &self,
+        equipments: &'a [Equipment],
+        now: DateTime<Utc>,
+        args: &DebounceArgs,
+    ) -> Vec<&'a Equipment> {
+        equipments
+            .iter()
+            .filter(|equipment| self.is_recently_broken(equipment, now, args))
+            .collect()
+    }
+
+    /// The `equipments` with those that are [`Self::recently_broken`] marked as such,
+    /// so they aren't alerted about, see [`Equipment::recently_broken`].
+    pub fn mark_recently_broken(
+        &self,
+        equipments: Vec<Equipment>,
+        now: DateTime<Utc>,
+        args: &DebounceArgs,
+    ) -> Vec<Equipment> {
+        equipments
+            .into_iter()
+            .map(|equipment| {
+                let recently_broken = self.is_recently_broken(&equipment, now, args);
+                equipment.with_recently_broken(recently_broken)
+            })
+            .collect()
+    }
+
+    fn is_recently_broken(
+        &self,
+        equipment: &Equipment,
+        now: DateTime<Utc>,
+        args: &DebounceArgs,
+    ) -> bool {
+        let Some(min_duration) = args.broken_min_duration else {
+            return false;
+        };
+        equipment.working == Some(false)
+            && self.equipments.get(&key(equipment)).is_some_and(|history| {
+                history.observed == Some(false)
+                    && (now - history.since).to_std().unwrap_or_default() < min_duration
+            })
+    }
+}
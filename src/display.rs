@@ -1,234 +1,1164 @@
-use crate::Equipment;
+use crate::{
+    data_dir::DataDir,
+    notifier::Notifier,
+    request_error, retry_with_backoff,
+    run::RunContext,
+    summary::{limit_listed, StatusSummary, UnknownAs},
+    Equipment,
+};
 
-use chrono::{Datelike, Timelike};
-use clap::Args;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, TimeZone,
+};
+use clap::{Args, ValueEnum};
 use image::{ImageOutputFormat, RgbImage};
+use qrcode::{types::QrError, Color, EcLevel, QrCode};
 use reqwest::blocking::{multipart::Form, Client};
 use rgb::ComponentBytes;
+use serde_json::Value;
 use slint::{
     platform::{software_renderer::*, Platform, PlatformError, WindowAdapter},
     Rgb8Pixel, SharedString, VecModel,
 };
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::HashMap,
     error::Error,
-    path::Path,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
     rc::Rc,
-    sync::Mutex,
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 const WIDTH: usize = 296;
 const HEIGHT: usize = 128;
 
+/// File names of the rendered images of the main and the secondary tag
+const MAIN_IMAGE: &str = "elstatus.jpg";
+const SECONDARY_IMAGE: &str = "elstatus_secondary.jpg";
+
+/// How the time of the last update is shown on the tags, e.g. "05.01. - 09:03"
+pub const DEFAULT_DATE_FORMAT: &str = "%d.%m. - %H:%M";
+
+/// Clap leaves the group of arguments empty because of the flattened structs,
+/// so the arguments every update needs join it explicitly.
+/// Otherwise, an optional `DisplayArgs` is never present.
 #[derive(Args, Debug)]
+#[group(id = "display")]
 pub struct DisplayArgs {
     /// URL or IP address of the access point.
-    #[clap(long)]
+    /// Uses http unless the address starts with a scheme, e.g. "https://ap.example.org".
+    #[clap(long, group = "display")]
     ap_address: String,
 
-    /// MAC of the E-Paper Tag
+    /// Accept invalid TLS certificates from the access point, e.g. self-signed ones in the local network
     #[clap(long)]
+    ap_accept_invalid_certs: bool,
+
+    /// MAC of the E-Paper Tag, e.g. "00:00:02:1C:4B:3F:3B:12" or "0000021C4B3F3B12"
+    #[clap(long, group = "display", value_parser = parse_mac)]
     main_tag: String,
 
     /// MAC of the secondary E-Paper Tag
-    #[clap(long)]
+    #[clap(long, group = "display", value_parser = parse_mac)]
     secondary_tag: String,
+
+    /// Timeout for each upload to the access point
+    #[clap(long, value_name = "SECONDS", default_value_t = 30)]
+    upload_timeout: u64,
+
+    /// After uploading, wait up to this long for both tags to pick up their new image.
+    /// Fails if a tag doesn't check in with the access point in time, e.g. because it is offline.
+    #[clap(long, value_name = "SECONDS")]
+    verify_timeout: Option<u64>,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Upload the images even if the status or the images didn't change since the last upload
+    #[clap(long)]
+    force_upload: bool,
+
+    /// Keep the uploaded images instead of deleting them afterwards. Their location is printed.
+    #[clap(long)]
+    save_images: bool,
+
+    #[command(flatten)]
+    upload_endpoint: UploadEndpoint,
+
+    #[command(flatten)]
+    upload_retries: UploadRetries,
+}
+
+/// How the images of the tags are laid out and encoded, shared by `display` and `render-only`.
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// How the time of the last update is shown on the tags, as a chrono/strftime format string
+    #[clap(long, default_value = DEFAULT_DATE_FORMAT, value_parser = parse_date_format)]
+    date_format: String,
+
+    /// Show a QR code linking to the station on wheelmap.org in the corner of the secondary tag
+    #[clap(long)]
+    qr_code: bool,
+
+    /// Show only the number of broken elevators and the most critical one on the secondary tag,
+    /// in large black text that is easier to read from a distance
+    #[clap(long)]
+    large_text: bool,
+
+    /// The order in which the broken elevators are listed on the tags
+    #[clap(long, value_enum, default_value_t = SortOrder::Station)]
+    sort: SortOrder,
+
+    /// Reduce the images to pure black and white before uploading them,
+    /// instead of leaving it to the access point, for tags without gray levels or red
+    #[clap(long, value_enum, default_value_t = Monochrome::Off)]
+    monochrome: Monochrome,
+
+    /// Pixels with a brightness below this become black with --monochrome, from 0 to 255
+    #[clap(long, default_value_t = DEFAULT_THRESHOLD)]
+    threshold: u8,
+
+    #[command(flatten)]
+    pub image_encoding: ImageEncoding,
+}
+
+impl RenderArgs {
+    /// The renderer for the images of the tags, with the layout options given on the command line.
+    /// Create it once and reuse it for every update.
+    pub fn renderer(&self) -> DisplayRenderer {
+        DisplayRenderer::new()
+            .with_date_format(&self.date_format)
+            .with_qr_code(self.qr_code)
+            .with_large_text(self.large_text)
+            .with_sort(self.sort)
+            .with_monochrome(self.monochrome, self.threshold)
+    }
+}
+
+impl DisplayArgs {
+    /// See [`RenderArgs::renderer`]
+    pub fn renderer(&self) -> DisplayRenderer {
+        self.render.renderer()
+    }
+
+    /// Everything that is wrong with the arguments, found without contacting the access point.
+    pub fn config_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(err) = reqwest::Url::parse(&ap_url(&self.ap_address, "/")) {
+            problems.push(format!(
+                "Invalid access point address {}: {}",
+                self.ap_address, err
+            ));
+        }
+        // The MACs themselves are already checked by `parse_mac`
+        if self.main_tag == self.secondary_tag {
+            problems.push("The main and the secondary tag are the same".to_owned());
+        }
+        problems
+    }
+}
+
+/// The order in which the broken elevators are listed on the tags, see [`broken_places`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortOrder {
+    /// By station, then by name
+    #[default]
+    Station,
+    /// By name, the stations in the order of their first elevator
+    Name,
+    /// The highest priority first, then by station and name
+    Priority,
+}
+
+/// How the images are reduced to black and white, see [`to_monochrome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Monochrome {
+    /// Keep the colors, the access point maps them to the colors of the tag
+    #[default]
+    Off,
+    /// Every pixel becomes black or white, depending on the threshold. Gives the crispest text.
+    Threshold,
+    /// Spread the error of every pixel onto its neighbours, so gray areas keep their shade
+    FloydSteinberg,
+}
+
+/// The brightness below which pixels become black, halfway between black and white.
+pub const DEFAULT_THRESHOLD: u8 = 128;
+
+/// Reduce the `image` to black and white by its brightness, so red text becomes black as well.
+/// Pixels darker than the `threshold` become black, and with [`Monochrome::FloydSteinberg`]
+/// the difference is passed on to the pixels right and below.
+pub fn to_monochrome(image: &RgbImage, mode: Monochrome, threshold: u8) -> RgbImage {
+    if mode == Monochrome::Off {
+        return image.clone();
+    }
+    let gray = image::imageops::grayscale(image);
+    let (width, height) = gray.dimensions();
+    let mut brightness: Vec<f32> = gray.pixels().map(|pixel| f32::from(pixel[0])).collect();
+    let mut monochrome = RgbImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let old = brightness[(y * width + x) as usize];
+            let new = if old < f32::from(threshold) {
+                0.0
+            } else {
+                255.0
+            };
+            monochrome.put_pixel(x, y, image::Rgb([new as u8; 3]));
+            if mode != Monochrome::FloydSteinberg {
+                continue;
+            }
+            let error = old - new;
+            for (dx, dy, weight) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if (0..width as i64).contains(&nx) && ny < height as i64 {
+                    brightness[(ny * width as i64 + nx) as usize] += error * weight / 16.0;
+                }
+            }
+        }
+    }
+    monochrome
+}
+
+/// Where and how images are uploaded to the access point.
+/// The defaults match the current OpenEPaperLink firmware, forks and older versions may differ.
+#[derive(Args, Debug, Clone)]
+pub struct UploadEndpoint {
+    /// Path of the image upload endpoint on the access point
+    #[clap(long = "upload-path", default_value = "/imgupload", value_parser = parse_endpoint_path)]
+    pub path: String,
+
+    /// Name of the form field with the MAC of the tag
+    #[clap(long = "upload-mac-field", default_value = "mac")]
+    pub mac_field: String,
+
+    /// Name of the form field that turns dithering on or off
+    #[clap(long = "upload-dither-field", default_value = "dither")]
+    pub dither_field: String,
+}
+
+impl Default for UploadEndpoint {
+    fn default() -> Self {
+        Self {
+            path: "/imgupload".to_owned(),
+            mac_field: "mac".to_owned(),
+            dither_field: "dither".to_owned(),
+        }
+    }
+}
+
+/// How the images are encoded before they are uploaded.
+/// Some access points with little memory reject images above a certain size.
+#[derive(Args, Debug, Clone)]
+pub struct ImageEncoding {
+    /// Quality of the JPEG images, from 1 to 100. Lower qualities make smaller files.
+    #[clap(long = "jpeg-quality", default_value_t = 100, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub quality: u8,
+
+    /// Warn if an encoded image is larger than this many bytes
+    #[clap(long = "max-image-size", value_name = "BYTES")]
+    pub max_size: Option<usize>,
+
+    /// Lower the JPEG quality of images larger than --max-image-size until they fit
+    #[clap(long = "reduce-quality", requires = "max_size")]
+    pub reduce_quality: bool,
 }
 
-struct MyPlatform {
-    main_tag: Rc<MinimalSoftwareWindow>,
-    secondary_tag: Rc<MinimalSoftwareWindow>,
-    index: Mutex<i32>,
+impl Default for ImageEncoding {
+    fn default() -> Self {
+        Self {
+            quality: 100,
+            max_size: None,
+            reduce_quality: false,
+        }
+    }
+}
+
+/// The lowest quality `--reduce-quality` goes down to, below it the text gets hard to read.
+const MIN_JPEG_QUALITY: u8 = 10;
+
+impl ImageEncoding {
+    /// Encode the `image` as JPEG, named `name` in the warnings about its size.
+    /// If it is larger than `max_size`, the quality is lowered in steps of 10 if `reduce_quality` is set.
+    /// An image that is still too large is only warned about, the access point may accept it anyway.
+    pub fn encode(&self, image: &RgbImage, name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut quality = self.quality;
+        let mut jpeg = encode_jpeg(image, quality)?;
+        let Some(max_size) = self.max_size else {
+            return Ok(jpeg);
+        };
+        if self.reduce_quality && jpeg.len() > max_size {
+            while jpeg.len() > max_size && quality > MIN_JPEG_QUALITY {
+                quality = quality.saturating_sub(10).max(MIN_JPEG_QUALITY);
+                jpeg = encode_jpeg(image, quality)?;
+            }
+            println!(
+                "🗜️ Reduced the JPEG quality of {} to {} to make it {} bytes",
+                name,
+                quality,
+                jpeg.len()
+            );
+        }
+        if jpeg.len() > max_size {
+            println!(
+                "⚠️ {} is {} bytes, more than the {} bytes allowed by --max-image-size, the access point may reject it",
+                name,
+                jpeg.len(),
+                max_size
+            );
+        }
+        Ok(jpeg)
+    }
+}
+
+fn encode_jpeg(image: &RgbImage, quality: u8) -> Result<Vec<u8>, image::ImageError> {
+    let mut jpeg = Vec::new();
+    image.write_to(
+        &mut Cursor::new(&mut jpeg),
+        ImageOutputFormat::Jpeg(quality),
+    )?;
+    Ok(jpeg)
 }
 
+/// How often a failed upload is retried.
+/// The delay doubles after every retry, so with `n` retries the uploads wait for at most
+/// `delay * (2^n - 1)` in total, i.e. 3.1 seconds with the defaults.
+#[derive(Args, Debug, Clone)]
+pub struct UploadRetries {
+    /// How often to retry a failed upload, 0 to give up right away
+    #[clap(long = "upload-retries", default_value_t = 5)]
+    pub retries: u32,
+
+    /// How long to wait before the first retry, e.g. "100ms". Doubles after every retry.
+    #[clap(long = "upload-retry-delay", default_value = "100ms", value_parser = humantime::parse_duration)]
+    pub delay: Duration,
+}
+
+impl Default for UploadRetries {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+/// The endpoint is appended to the access point's address, so it must be an absolute path.
+fn parse_endpoint_path(path: &str) -> Result<String, String> {
+    if !path.starts_with('/') || path.contains("://") || path.contains(char::is_whitespace) {
+        return Err(format!("Expected a path like /imgupload, got: {}", path));
+    }
+    Ok(path.to_owned())
+}
+
+/// Parse the MAC of a tag: 6 or 8 bytes in hex, either separated by colons or dashes,
+/// e.g. "00:00:02:1C:4B:3F:3B:12", or without separators.
+/// Returns it as the access point expects it, see [`normalize_mac`].
+pub fn parse_mac(mac: &str) -> Result<String, String> {
+    let bytes: Vec<String> = if mac.contains([':', '-']) {
+        mac.split([':', '-']).map(str::to_owned).collect()
+    } else {
+        let digits: Vec<_> = mac.chars().collect();
+        digits.chunks(2).map(|byte| byte.iter().collect()).collect()
+    };
+    let valid = [6, 8].contains(&bytes.len())
+        && bytes
+            .iter()
+            .all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(format!(
+            "Expected a MAC of 6 or 8 bytes in hex like 00:00:02:1C:4B:3F:3B:12 or 0000021C4B3F3B12, got: {}",
+            mac
+        ));
+    }
+    Ok(normalize_mac(mac))
+}
+
+fn parse_date_format(format: &str) -> Result<String, String> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(format!("Invalid date format: {}", format));
+    }
+    Ok(format.to_owned())
+}
+
+/// Format the time of the last update for the tags, see [`DEFAULT_DATE_FORMAT`].
+pub fn format_last_update<Tz: TimeZone>(time: &DateTime<Tz>, format: &str) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    time.format(format).to_string()
+}
+
+thread_local! {
+    /// The window most recently created by [`MyPlatform`].
+    static LAST_WINDOW: RefCell<Option<Rc<MinimalSoftwareWindow>>> = const { RefCell::new(None) };
+}
+
+/// Creates a fresh window with its own buffer for every component,
+/// so rendering can be repeated within the same process.
+struct MyPlatform;
+
 impl Platform for MyPlatform {
     fn create_window_adapter(&self) -> Result<Rc<dyn WindowAdapter>, PlatformError> {
-        let mut index = self.index.lock().unwrap();
-        let current = *index;
-        *index += 1;
-        match current {
-            0 => Ok(self.main_tag.clone()),
-            1 => Ok(self.secondary_tag.clone()),
-            _ => Err(PlatformError::Other("No more available tags!".to_owned())),
-        }
+        let window = MinimalSoftwareWindow::new(RepaintBufferType::NewBuffer);
+        window.set_size(slint::PhysicalSize::new(WIDTH as u32, HEIGHT as u32));
+        LAST_WINDOW.with(|last| *last.borrow_mut() = Some(Rc::clone(&window)));
+        Ok(window)
+    }
+}
+
+/// Slint keeps the platform per thread and only allows setting it once,
+/// so register it on first use in each thread and reuse it for every render.
+fn init_platform() {
+    thread_local! {
+        static INITIALIZED: Cell<bool> = const { Cell::new(false) };
     }
+    if !INITIALIZED.replace(true) {
+        slint::platform::set_platform(Box::new(MyPlatform)).unwrap();
+    }
+}
+
+fn take_last_window() -> Rc<MinimalSoftwareWindow> {
+    LAST_WINDOW
+        .with(|last| last.borrow_mut().take())
+        .expect("Component was shown without creating a window!")
+}
+
+const STATE_FILE: &str = "elstatus.state.json";
+
+/// The equipments shown on the displays after the last successful update, if known.
+pub fn load_state(data_dir: &DataDir) -> Option<Vec<Equipment>> {
+    let equipments_json = std::fs::read_to_string(data_dir.state_file(STATE_FILE)).ok()?;
+    serde_json::de::from_str(&equipments_json).ok()
+}
+
+fn store_state(data_dir: &DataDir, equipments: &[Equipment]) -> Result<(), Box<dyn Error>> {
+    std::fs::write(
+        data_dir.state_file(STATE_FILE),
+        serde_json::ser::to_string(equipments)?,
+    )?;
+    Ok(())
 }
 
-const STATE_FILE: &str = "/tmp/elstatus.state.json";
+/// The hash of the image last uploaded to each tag, keyed by MAC.
+/// Every refresh wears the e-paper, so identical images aren't uploaded again.
+const UPLOADS_FILE: &str = "elstatus.uploads.json";
 
-fn has_changed(equipments: &[Equipment]) -> Result<bool, Box<dyn Error>> {
-    let equipments_json = std::fs::read_to_string(STATE_FILE)?;
-    Ok(serde_json::de::from_str::<Vec<Equipment>>(&equipments_json)? != equipments)
+fn load_uploaded_hashes(data_dir: &DataDir) -> HashMap<String, String> {
+    std::fs::read_to_string(data_dir.state_file(UPLOADS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
 }
 
-fn store_state(equipments: &[Equipment]) -> Result<(), Box<dyn Error>> {
-    std::fs::write(STATE_FILE, serde_json::ser::to_string(equipments)?)?;
+fn store_uploaded_hashes(
+    data_dir: &DataDir,
+    hashes: &HashMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(
+        data_dir.state_file(UPLOADS_FILE),
+        serde_json::to_string(hashes)?,
+    )?;
     Ok(())
 }
 
-pub fn update(equipments: &[Equipment], args: &DisplayArgs) -> Result<(), Box<dyn Error>> {
-    if !has_changed(equipments).unwrap_or(true) {
+/// Whether any image was uploaded by an update that kept its state in `data_dir`.
+pub fn uploaded_any(data_dir: &DataDir) -> bool {
+    !load_uploaded_hashes(data_dir).is_empty()
+}
+
+/// Forget what the displays show and which images were uploaded to them,
+/// so the next update uploads the images again, e.g. after `--send-test` showed something else.
+pub fn forget_state(data_dir: &DataDir) -> io::Result<()> {
+    for file in [STATE_FILE, UPLOADS_FILE] {
+        match std::fs::remove_file(data_dir.state_file(file)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A hash of the `image` and how it is encoded that stays the same across runs and versions,
+/// so it can be compared with the hash of the last uploaded image.
+/// Meant for the images of [`DisplayRenderer::render_unstamped`], as the time of the last update
+/// would make every image differ from the last one.
+pub fn image_hash(image: &RgbImage, encoding: &ImageEncoding) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(image.as_raw());
+    hasher.update(
+        format!(
+            "{}x{}:{}:{:?}:{}",
+            image.width(),
+            image.height(),
+            encoding.quality,
+            encoding.max_size,
+            encoding.reduce_quality
+        )
+        .as_bytes(),
+    );
+    hasher.finalize().to_hex().to_string()
+}
+
+pub fn update(
+    equipments: &[Equipment],
+    args: &DisplayArgs,
+    renderer: &DisplayRenderer,
+    run: &RunContext,
+    data_dir: &DataDir,
+) -> Result<Option<(RgbImage, RgbImage)>, Box<dyn Error>> {
+    update_since(
+        equipments,
+        load_state(data_dir).as_deref(),
+        args,
+        renderer,
+        run,
+        data_dir,
+    )
+}
+
+/// Update the displays, unless `equipments` is the same as the `previous` state.
+/// The images are rendered by the `renderer`, usually [`DisplayArgs::renderer`],
+/// with the start of the `run` as the last update.
+/// They are written into the `data_dir` and the state is kept there.
+/// Returns the rendered images, or `None` if nothing had to be rendered.
+pub fn update_since(
+    equipments: &[Equipment],
+    previous: Option<&[Equipment]>,
+    args: &DisplayArgs,
+    renderer: &DisplayRenderer,
+    run: &RunContext,
+    data_dir: &DataDir,
+) -> Result<Option<(RgbImage, RgbImage)>, Box<dyn Error>> {
+    if previous == Some(equipments) && !args.force_upload {
         println!("🔁 No change in state detected, skipping update");
-        return Ok(());
+        return Ok(None);
     }
 
-    render_ui(equipments);
+    let images = renderer.render(equipments, &run.started_at);
+    // Only a change of what the tags show is worth a refresh, not the time of the update alone
+    let (main_content, secondary_content) = renderer.render_unstamped(equipments);
 
-    upload_image(&args.ap_address, &args.main_tag, "elstatus.jpg")?;
-    println!("⏳ Waiting 10 seconds before uploading secondary image");
-    std::thread::sleep(Duration::from_secs(10));
-    upload_image(
-        &args.ap_address,
-        &args.secondary_tag,
-        "elstatus_secondary.jpg",
-    )?;
+    // A directory of its own, so instances running side by side don't overwrite each other's images
+    let mut image_dir = tempfile::Builder::new();
+    image_dir.prefix("elstatus-");
+    let image_dir = match data_dir.dir() {
+        Some(dir) => image_dir.tempdir_in(dir)?,
+        None => image_dir.tempdir()?,
+    };
+    let (main_image, secondary_image) =
+        write_images(image_dir.path(), &images, &args.render.image_encoding)?;
+    if args.save_images {
+        println!("🖼️ Images saved to {}", image_dir.keep().display());
+    }
+
+    let uploaded_at = SystemTime::now();
+    let timeout = Duration::from_secs(args.upload_timeout);
+    let mut hashes = load_uploaded_hashes(data_dir);
+    let mut uploaded = Vec::new();
+    for (tag_mac, image, content) in [
+        (&args.main_tag, &main_image, &main_content),
+        (&args.secondary_tag, &secondary_image, &secondary_content),
+    ] {
+        let hash = image_hash(content, &args.render.image_encoding);
+        if !args.force_upload && hashes.get(&normalize_mac(tag_mac)) == Some(&hash) {
+            println!(
+                "🔁 Image for tag {} didn't change, skipping upload",
+                tag_mac
+            );
+            continue;
+        }
+        if !uploaded.is_empty() {
+            println!("⏳ Waiting 10 seconds before uploading the next image");
+            std::thread::sleep(Duration::from_secs(10));
+        }
+        upload_image(
+            &args.ap_address,
+            tag_mac,
+            image,
+            &args.upload_endpoint,
+            &args.upload_retries,
+            timeout,
+            args.ap_accept_invalid_certs,
+        )?;
+        hashes.insert(normalize_mac(tag_mac), hash);
+        store_uploaded_hashes(data_dir, &hashes).ok();
+        uploaded.push(tag_mac);
+    }
+
+    if let Some(verify_timeout) = args.verify_timeout {
+        let verify_timeout = Duration::from_secs(verify_timeout);
+        for tag_mac in uploaded {
+            verify_upload(
+                &args.ap_address,
+                tag_mac,
+                uploaded_at,
+                verify_timeout,
+                args.ap_accept_invalid_certs,
+            )?;
+        }
+    }
 
     // Only update the state if the update succeeded
-    store_state(equipments).ok();
+    store_state(data_dir, equipments).ok();
+
+    Ok(Some(images))
+}
+
+/// The images the displays were updated with during a run,
+/// kept so other notifiers can show the same without rendering them again.
+pub type UpdatedImages = RefCell<Option<(RgbImage, RgbImage)>>;
+
+/// Updates the displays if the status changed since the last update.
+pub struct DisplayNotifier<'a> {
+    pub args: &'a DisplayArgs,
+    /// What the displays currently show, if known, see [`update_since`].
+    /// Kept in memory between the runs of the daemon, or read with [`load_state`].
+    pub previous: Option<&'a [Equipment]>,
+    /// Receives the images if the displays were updated
+    pub updated: &'a UpdatedImages,
+    pub renderer: &'a DisplayRenderer,
+    pub run: &'a RunContext,
+    pub data_dir: &'a DataDir,
+}
+
+impl Notifier for DisplayNotifier<'_> {
+    fn name(&self) -> &str {
+        "Display"
+    }
 
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        _errors: &[Box<dyn Error>],
+        _summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(images) = update_since(
+            equipments,
+            self.previous,
+            self.args,
+            self.renderer,
+            self.run,
+            self.data_dir,
+        )? {
+            *self.updated.borrow_mut() = Some(images);
+        }
+        Ok(())
+    }
+}
+
+/// Render the images for both tags into the `data_dir` without uploading them.
+/// Useful for working on the layout without an access point.
+/// The images are encoded as they would be uploaded, see [`ImageEncoding`].
+pub fn render(
+    equipments: &[Equipment],
+    renderer: &DisplayRenderer,
+    encoding: &ImageEncoding,
+    run: &RunContext,
+    data_dir: &DataDir,
+) -> Result<(), Box<dyn Error>> {
+    let images = renderer.render(equipments, &run.started_at);
+    let (main_image, secondary_image) = write_images(data_dir.image_dir(), &images, encoding)?;
+    println!(
+        "🖼️ Images written to {} and {}",
+        main_image.display(),
+        secondary_image.display()
+    );
     Ok(())
 }
 
-fn write_frame_buffer_to<P: AsRef<Path>>(path: P, frame_buffer: &[Rgb8Pixel]) -> RgbImage {
-    let frame_buffer = Vec::from(frame_buffer.as_bytes());
-    let image = image::RgbImage::from_raw(WIDTH as u32, HEIGHT as u32, frame_buffer).unwrap();
-    let mut file = std::fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)
-        .unwrap();
-    image
-        .write_to(&mut file, ImageOutputFormat::Jpeg(100))
-        .unwrap();
-    image
-}
-
-fn render_ui(equipments: &[Equipment]) -> (RgbImage, RgbImage) {
-    println!("💻 Rendering GUI");
-    let mut places = HashMap::<String, Vec<String>>::new();
-    for equipment in equipments.iter().filter(|eq| !eq.working.unwrap_or(false)) {
-        match places.entry(equipment.place.clone().unwrap_or_default()) {
-            Entry::Occupied(mut occupied_entry) => {
-                occupied_entry.get_mut().push(equipment.name.clone())
-            }
-            Entry::Vacant(vacant_entry) => {
-                vacant_entry.insert(vec![equipment.name.clone()]);
+/// Write the images of the main and the secondary tag into `dir` and return their paths.
+pub fn write_images(
+    dir: &Path,
+    (main, secondary): &(RgbImage, RgbImage),
+    encoding: &ImageEncoding,
+) -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    let main_path = dir.join(MAIN_IMAGE);
+    let secondary_path = dir.join(SECONDARY_IMAGE);
+    std::fs::write(&main_path, encoding.encode(main, MAIN_IMAGE)?)?;
+    std::fs::write(
+        &secondary_path,
+        encoding.encode(secondary, SECONDARY_IMAGE)?,
+    )?;
+    Ok((main_path, secondary_path))
+}
+
+/// The wheelmap.org page around the first of the `equipments` with known coordinates.
+pub fn wheelmap_url(equipments: &[Equipment]) -> Option<String> {
+    let [longitude, latitude] = equipments.iter().find_map(Equipment::coordinates)?;
+    Some(format!(
+        "https://wheelmap.org/?lat={latitude:.5}&lon={longitude:.5}&zoom=18"
+    ))
+}
+
+/// Size of a QR code module in pixels, the smallest that phones still read reliably from the tags.
+const QR_MODULE_SIZE: u32 = 2;
+/// The white border around the QR code, in modules
+const QR_QUIET_ZONE: u32 = 2;
+
+/// A QR code encoding `url`, with a white border around it.
+pub fn qr_code_image(url: &str) -> Result<RgbImage, QrError> {
+    let code = QrCode::with_error_correction_level(url, EcLevel::L)?;
+    let modules = code.width() as u32;
+    let size = (modules + 2 * QR_QUIET_ZONE) * QR_MODULE_SIZE;
+
+    Ok(RgbImage::from_fn(size, size, |x, y| {
+        let module = (
+            (x / QR_MODULE_SIZE).checked_sub(QR_QUIET_ZONE),
+            (y / QR_MODULE_SIZE).checked_sub(QR_QUIET_ZONE),
+        );
+        let dark = match module {
+            (Some(mx), Some(my)) if mx < modules && my < modules => {
+                code[(mx as usize, my as usize)] == Color::Dark
             }
+            _ => false,
+        };
+        if dark {
+            image::Rgb([0, 0, 0])
+        } else {
+            image::Rgb([255, 255, 255])
         }
+    }))
+}
+
+/// Draw a QR code encoding `url` into the bottom right corner of `image`, see [`qr_code_image`].
+pub fn draw_qr_code(image: &mut RgbImage, url: &str) -> Result<(), QrError> {
+    draw_in_corner(image, &qr_code_image(url)?);
+    Ok(())
+}
+
+fn draw_in_corner(image: &mut RgbImage, corner: &RgbImage) {
+    let left = image.width().saturating_sub(corner.width());
+    let top = image.height().saturating_sub(corner.height());
+    image::imageops::replace(image, corner, left.into(), top.into());
+}
+
+fn frame_buffer_to_image(frame_buffer: &[Rgb8Pixel]) -> RgbImage {
+    let frame_buffer = Vec::from(frame_buffer.as_bytes());
+    image::RgbImage::from_raw(WIDTH as u32, HEIGHT as u32, frame_buffer).unwrap()
+}
+
+/// Whether the `equipment` is listed as out of service on the tags and counted in their badge.
+/// Unlike notifications, this includes those with a low priority, in maintenance or recently broken,
+/// as they are out of service all the same. Those with an unknown status are listed as well,
+/// unless `--unknown-as working` is given.
+pub fn shown_as_broken(equipment: &Equipment, unknown_as: Option<UnknownAs>) -> bool {
+    match equipment.working {
+        Some(working) => !working,
+        None => unknown_as != Some(UnknownAs::Working),
     }
-    let places: Vec<_> = places
-        .iter()
+}
+
+/// The broken elevator to single out in the large text mode:
+/// the one with the highest priority, or the first one listed on the tag if several share it.
+pub fn most_critical(equipments: &[Equipment], sort: SortOrder) -> Option<&Equipment> {
+    listed_places(equipments, sort)
+        .into_iter()
+        .flat_map(|(_, equipments)| equipments)
+        .min_by_key(|eq| Reverse(eq.priority))
+}
+
+/// The broken `equipments` grouped by their station, in the given order.
+/// Sorting makes the tags look the same from run to run, so unchanged images don't have to be uploaded again.
+pub fn broken_places(equipments: &[Equipment], sort: SortOrder) -> Vec<(String, Vec<String>)> {
+    listed_places(equipments, sort)
+        .into_iter()
         .map(|(place, equipments)| {
-            let elevators: Vec<_> = equipments
-                .iter()
-                .map(|elevator| SharedString::from(elevator))
-                .collect();
-            Place {
-                place: place.into(),
-                elevators: Rc::new(VecModel::from(elevators)).into(),
-            }
+            let names = equipments.iter().map(|eq| eq.name.clone()).collect();
+            (place, names)
         })
+        .collect()
+}
+
+/// The broken `equipments` grouped by their station, in the order they are listed on the tag.
+fn listed_places(equipments: &[Equipment], sort: SortOrder) -> Vec<(String, Vec<&Equipment>)> {
+    let mut broken: Vec<_> = equipments
+        .iter()
+        .filter(|eq| !eq.working.unwrap_or(false))
+        .map(|eq| (display_place(eq).cloned().unwrap_or_default(), eq))
         .collect();
-    let now = chrono::Local::now();
-    let day = now.day();
-    let month = now.month();
-    let hour = now.hour();
-    let minute = now.minute();
-
-    let last_update = format!("{day:0>2}.{month:0>2}. - {hour:0>2}:{minute:0>2}");
-
-    let mut main_tag_fb = vec![Rgb8Pixel::default(); WIDTH * HEIGHT];
-    let mut secondary_tag_fb = vec![Rgb8Pixel::default(); WIDTH * HEIGHT];
-
-    let platform = Box::new(MyPlatform {
-        main_tag: MinimalSoftwareWindow::new(RepaintBufferType::NewBuffer),
-        secondary_tag: MinimalSoftwareWindow::new(RepaintBufferType::NewBuffer),
-        index: Mutex::new(0),
-    });
-
-    let main_window = Rc::clone(&platform.main_tag);
-    main_window.set_size(slint::PhysicalSize::new(WIDTH as u32, HEIGHT as u32));
-    let secondary_window = Rc::clone(&platform.secondary_tag);
-    secondary_window.set_size(slint::PhysicalSize::new(WIDTH as u32, HEIGHT as u32));
-
-    slint::platform::set_platform(platform).unwrap();
-
-    let main_tag = ElStatus::new().unwrap();
-    let vec_model = Rc::new(VecModel::from(places));
-    main_tag.set_broken(Rc::clone(&vec_model).into());
-    main_tag.set_last_update(last_update.clone().into());
-    main_tag.set_main(true);
-    main_tag.show().unwrap();
-
-    let secondary_tag = ElStatus::new().unwrap();
-    secondary_tag.set_broken(vec_model.into());
-    secondary_tag.set_last_update(last_update.into());
-    secondary_tag.set_main(false);
-    secondary_tag.show().unwrap();
-
-    slint::platform::update_timers_and_animations();
-
-    for (window, fb) in [
-        (main_window, &mut main_tag_fb),
-        (secondary_window, &mut secondary_tag_fb),
-    ] {
-        window.draw_if_needed(|software_renderer| {
-            software_renderer.render(fb, WIDTH);
-        });
+    match sort {
+        SortOrder::Station => {
+            broken.sort_by(|(place_a, a), (place_b, b)| (place_a, &a.name).cmp(&(place_b, &b.name)))
+        }
+        SortOrder::Name => {
+            broken.sort_by(|(place_a, a), (place_b, b)| (&a.name, place_a).cmp(&(&b.name, place_b)))
+        }
+        SortOrder::Priority => broken.sort_by(|(place_a, a), (place_b, b)| {
+            (Reverse(a.priority), place_a, &a.name).cmp(&(Reverse(b.priority), place_b, &b.name))
+        }),
+    }
+
+    let mut places: Vec<(String, Vec<&Equipment>)> = Vec::new();
+    for (place, equipment) in broken {
+        match places.iter_mut().find(|(other, _)| *other == place) {
+            Some((_, listed)) => listed.push(equipment),
+            None => places.push((place, vec![equipment])),
+        }
+    }
+    places
+}
+
+/// Prefer the configured station label over the API's place name
+fn display_place(equipment: &Equipment) -> Option<&String> {
+    equipment.station.as_ref().or(equipment.place.as_ref())
+}
+
+/// Renders the images of the main and the secondary tag, see [`write_images`] to save them.
+/// The UI is set up once, so a long-running process can render with the same renderer over and over.
+pub struct DisplayRenderer {
+    main_tag: ElStatus,
+    secondary_tag: ElStatus,
+    main_window: Rc<MinimalSoftwareWindow>,
+    secondary_window: Rc<MinimalSoftwareWindow>,
+    /// Shared by both tags, only its rows change between renders
+    broken: Rc<VecModel<Place>>,
+    date_format: String,
+    qr_code: bool,
+    large_text: bool,
+    sort: SortOrder,
+    max_listed: Option<usize>,
+    unknown_as: Option<UnknownAs>,
+    monochrome: Monochrome,
+    threshold: u8,
+}
+
+impl DisplayRenderer {
+    pub fn new() -> Self {
+        init_platform();
+        let broken = Rc::new(VecModel::default());
+
+        let main_tag = ElStatus::new().unwrap();
+        main_tag.set_broken(Rc::clone(&broken).into());
+        main_tag.set_main(true);
+        main_tag.show().unwrap();
+        let main_window = take_last_window();
+
+        let secondary_tag = ElStatus::new().unwrap();
+        secondary_tag.set_broken(Rc::clone(&broken).into());
+        secondary_tag.set_main(false);
+        secondary_tag.show().unwrap();
+        let secondary_window = take_last_window();
+
+        Self {
+            main_tag,
+            secondary_tag,
+            main_window,
+            secondary_window,
+            broken,
+            date_format: DEFAULT_DATE_FORMAT.to_owned(),
+            qr_code: false,
+            large_text: false,
+            sort: SortOrder::default(),
+            max_listed: None,
+            unknown_as: None,
+            monochrome: Monochrome::Off,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// How the time of the last update is shown, see [`format_last_update`].
+    pub fn with_date_format(mut self, date_format: &str) -> Self {
+        self.date_format = date_format.to_owned();
+        self
+    }
+
+    /// Draw a QR code linking to the station on wheelmap.org onto the secondary tag, see [`wheelmap_url`].
+    pub fn with_qr_code(mut self, qr_code: bool) -> Self {
+        self.qr_code = qr_code;
+        self
+    }
+
+    /// Show the number of broken elevators and the most critical one in large text on the secondary tag.
+    pub fn with_large_text(mut self, large_text: bool) -> Self {
+        self.large_text = large_text;
+        self
+    }
+
+    /// The order of the broken elevators, see [`broken_places`].
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Only list the `max_listed` most important broken elevators on the secondary tag, see [`limit_listed`].
+    /// Those with an unknown status count towards it as well if they are listed, see [`shown_as_broken`].
+    pub fn with_max_listed(mut self, max_listed: Option<usize>) -> Self {
+        self.max_listed = max_listed;
+        self
     }
 
-    let main_image = write_frame_buffer_to("elstatus.jpg", &main_tag_fb);
-    let secondary_image = write_frame_buffer_to("elstatus_secondary.jpg", &secondary_tag_fb);
+    /// Whether elevators with an unknown status are shown as broken, see [`shown_as_broken`].
+    pub fn with_unknown_as(mut self, unknown_as: Option<UnknownAs>) -> Self {
+        self.unknown_as = unknown_as;
+        self
+    }
+
+    /// Reduce the images to black and white, see [`to_monochrome`].
+    pub fn with_monochrome(mut self, monochrome: Monochrome, threshold: u8) -> Self {
+        self.monochrome = monochrome;
+        self.threshold = threshold;
+        self
+    }
+
+    /// Render the status of `equipments`, last updated at `updated_at`.
+    pub fn render(
+        &self,
+        equipments: &[Equipment],
+        updated_at: &DateTime<Local>,
+    ) -> (RgbImage, RgbImage) {
+        println!("💻 Rendering GUI");
+        self.render_with_last_update(
+            equipments,
+            format_last_update(updated_at, &self.date_format).into(),
+        )
+    }
+
+    /// Render the status of `equipments` like [`DisplayRenderer::render`], but without the time of the last update,
+    /// so the images stay the same as long as the status does, see [`image_hash`].
+    pub fn render_unstamped(&self, equipments: &[Equipment]) -> (RgbImage, RgbImage) {
+        self.render_with_last_update(equipments, SharedString::default())
+    }
+
+    fn render_with_last_update(
+        &self,
+        equipments: &[Equipment],
+        last_update: SharedString,
+    ) -> (RgbImage, RgbImage) {
+        // The badge, the all clear and the list all go by the same elevators
+        let broken: Vec<_> = equipments
+            .iter()
+            .filter(|equipment| shown_as_broken(equipment, self.unknown_as))
+            .cloned()
+            .collect();
+        let (listed, more_broken) = limit_listed(&broken, self.max_listed, |_| true);
+        let places: Vec<_> = broken_places(&listed, self.sort)
+            .into_iter()
+            .map(|(place, equipments)| {
+                let elevators: Vec<_> = equipments.iter().map(SharedString::from).collect();
+                Place {
+                    place: place.into(),
+                    elevators: Rc::new(VecModel::from(elevators)).into(),
+                }
+            })
+            .collect();
+        self.broken.set_vec(places);
+
+        self.main_tag.set_last_update(last_update.clone());
+        self.secondary_tag.set_last_update(last_update);
+
+        let num_broken = broken.len();
+        self.main_tag.set_num_broken(num_broken as i32);
+        // Without any elevator, nothing is listed either, but that may be a mistake in the list
+        self.secondary_tag
+            .set_all_clear(num_broken == 0 && !equipments.is_empty());
+        self.secondary_tag.set_num_broken(num_broken as i32);
+        self.secondary_tag.set_more_broken(more_broken as i32);
+
+        // The layout leaves the corner of the QR code free, so nothing is drawn over the list
+        let qr_code = self
+            .qr_code
+            .then(|| wheelmap_url(equipments))
+            .flatten()
+            .and_then(|url| {
+                qr_code_image(&url)
+                    .map_err(|err| println!("⚠️ Could not draw the QR code: {}", err))
+                    .ok()
+            });
+        self.secondary_tag
+            .set_qr_code(qr_code.as_ref().map_or(0.0, |code| code.width() as f32));
+
+        if self.large_text {
+            let critical = most_critical(&broken, self.sort);
+            self.secondary_tag.set_large_text(true);
+            self.secondary_tag.set_critical_elevator(
+                critical
+                    .map(|critical| critical.name.as_str())
+                    .unwrap_or_default()
+                    .into(),
+            );
+            self.secondary_tag.set_critical_place(
+                critical
+                    .and_then(display_place)
+                    .map(String::as_str)
+                    .unwrap_or_default()
+                    .into(),
+            );
+        }
+
+        slint::platform::update_timers_and_animations();
+
+        let [main_image, mut secondary_image] =
+            [&self.main_window, &self.secondary_window].map(|window| {
+                let mut frame_buffer = vec![Rgb8Pixel::default(); WIDTH * HEIGHT];
+                // Every render starts from a fresh buffer, so the whole window has to be drawn again
+                window.request_redraw();
+                window.draw_if_needed(|software_renderer| {
+                    software_renderer.render(&mut frame_buffer, WIDTH);
+                });
+                frame_buffer_to_image(&frame_buffer)
+            });
 
-    (main_image, secondary_image)
+        if let Some(qr_code) = &qr_code {
+            draw_in_corner(&mut secondary_image, qr_code);
+        }
+        (
+            to_monochrome(&main_image, self.monochrome, self.threshold),
+            to_monochrome(&secondary_image, self.monochrome, self.threshold),
+        )
+    }
+}
+
+impl Default for DisplayRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The URL of `path` on the access point. `ap_address` may include a scheme, http is used otherwise.
+fn ap_url(ap_address: &str, path: &str) -> String {
+    let ap_address = ap_address.trim_end_matches('/');
+    if ap_address.contains("://") {
+        format!("{}{}", ap_address, path)
+    } else {
+        format!("http://{}{}", ap_address, path)
+    }
+}
+
+fn ap_client(timeout: Duration, accept_invalid_certs: bool) -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(timeout)
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
 }
 
 fn try_uploading(
     ap_address: &str,
     tag_mac: &str,
     client: &Client,
-    image_path: &str,
+    image_path: &Path,
+    endpoint: &UploadEndpoint,
+    timeout: Duration,
 ) -> Result<(), Box<dyn Error>> {
+    // The field is named after the file, e.g. "elstatus.jpg"
+    let field_name = image_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
     let form = Form::new()
-        .text("mac", tag_mac.to_owned())
-        .text("dither", "0")
-        .file(image_path.to_owned(), image_path)?;
+        .text(endpoint.mac_field.clone(), tag_mac.to_owned())
+        .text(endpoint.dither_field.clone(), "0")
+        .file(field_name, image_path)?;
     let request = client
-        .post(format!("http://{}/imgupload", ap_address))
+        .post(ap_url(ap_address, &endpoint.path))
         .multipart(form)
-        .send()?;
+        .send()
+        .map_err(|err| request_error(err, timeout))?;
 
     request.error_for_status()?;
     Ok(())
 }
 
-fn upload_image(ap_address: &str, tag_mac: &str, image_path: &str) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
+/// Upload the image at `image_path` to the tag, retrying with exponential backoff.
+pub fn upload_image(
+    ap_address: &str,
+    tag_mac: &str,
+    image_path: &Path,
+    endpoint: &UploadEndpoint,
+    retries: &UploadRetries,
+    timeout: Duration,
+    accept_invalid_certs: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = ap_client(timeout, accept_invalid_certs)?;
 
-    let mut delay = Duration::from_millis(100);
-    let mut last_result = Ok(());
-    const NUM_RETRIES: i32 = 5;
-    for i in 1..NUM_RETRIES + 1 {
+    retry_with_backoff(retries.retries + 1, retries.delay, || {
         println!("📶 Uploading");
-        let result = try_uploading(ap_address, tag_mac, &client, image_path);
-        if result.is_ok() {
-            println!("✅ Successfully uploaded");
+        try_uploading(ap_address, tag_mac, &client, image_path, endpoint, timeout)
+    })?;
+    println!("✅ Successfully uploaded");
+    Ok(())
+}
+
+/// How often the access point is asked whether a tag picked up its image.
+const VERIFY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The access point lists MACs as upper case hex without separators.
+fn normalize_mac(mac: &str) -> String {
+    mac.chars()
+        .filter(char::is_ascii_hexdigit)
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Poll the access point's tag database until the tag checked in after `uploaded_at`
+/// and has no pending image left, which means it received the uploaded image.
+pub fn verify_upload(
+    ap_address: &str,
+    tag_mac: &str,
+    uploaded_at: SystemTime,
+    timeout: Duration,
+    accept_invalid_certs: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = ap_client(VERIFY_INTERVAL, accept_invalid_certs)?;
+    let uploaded_at = uploaded_at.duration_since(UNIX_EPOCH)?.as_secs();
+    let mac = normalize_mac(tag_mac);
+    let started = Instant::now();
+
+    loop {
+        let db: Value = client
+            .get(ap_url(ap_address, "/get_db"))
+            .query(&[("mac", &mac)])
+            .send()
+            .map_err(|err| request_error(err, VERIFY_INTERVAL))?
+            .error_for_status()?
+            .json()?;
+        let tag = db
+            .get("tags")
+            .and_then(Value::as_array)
+            .and_then(|tags| {
+                tags.iter().find(|tag| {
+                    tag.get("mac")
+                        .and_then(Value::as_str)
+                        .is_some_and(|tag_mac| normalize_mac(tag_mac) == mac)
+                })
+            })
+            .ok_or_else(|| format!("Tag {} is unknown to the access point", tag_mac))?;
+
+        let last_seen = tag.get("lastseen").and_then(Value::as_u64).unwrap_or(0);
+        let pending = tag.get("pending").and_then(Value::as_bool).unwrap_or(true);
+        if last_seen >= uploaded_at && !pending {
+            println!("✅ Tag {} received the new image", tag_mac);
             return Ok(());
         }
 
-        last_result = result;
-        println!(
-            "⚠️ {i}/{NUM_RETRIES} upload failed - ⏳ retrying in {} ms!",
-            delay.as_millis()
-        );
-        std::thread::sleep(delay);
-        delay *= 2;
+        if started.elapsed() + VERIFY_INTERVAL > timeout {
+            return Err(format!(
+                "Tag {} did not pick up the new image within {} seconds, it may be offline",
+                tag_mac,
+                timeout.as_secs()
+            )
+            .into());
+        }
+        println!("⏳ Waiting for tag {} to pick up the new image", tag_mac);
+        std::thread::sleep(VERIFY_INTERVAL);
     }
+}
 
-    last_result
+/// Check that the access point's web interface responds.
+pub fn check_access_point(
+    ap_address: &str,
+    timeout: Duration,
+    accept_invalid_certs: bool,
+) -> Result<(), Box<dyn Error>> {
+    ap_client(timeout, accept_invalid_certs)?
+        .get(ap_url(ap_address, "/"))
+        .send()
+        .map_err(|err| request_error(err, timeout))?
+        .error_for_status()?;
+    Ok(())
 }
 
 slint::include_modules!();
@@ -1,8 +1,27 @@
-use crate::Equipment;
+use crate::{
+    change::{status_changes, StatusChange},
+    data_dir::DataDir,
+    display, group_by_station,
+    notifier::Notifier,
+    retry_with_backoff_if,
+    run::RunContext,
+    structured_error,
+    summary::{limit_broken, StatusSummary},
+    Equipment,
+};
 use clap::Args;
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{message::MultiPart, Message, SmtpTransport, Transport};
-use std::error::Error;
+use image::{ImageOutputFormat, RgbImage};
+use lettre::transport::smtp::{
+    self,
+    authentication::{Credentials, Mechanism},
+};
+use lettre::{
+    message::{header::ContentType, Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart},
+    Message, SmtpTransport, Transport,
+};
+use std::{
+    cmp::Ordering, error::Error, fmt::Display, io::Cursor, path::Path, str::FromStr, time::Duration,
+};
 use tera::Tera;
 
 #[derive(Args, Debug)]
@@ -23,114 +42,598 @@ pub struct EMailArgs {
     #[clap(long)]
     smtp_user: String,
 
-    /// smtp password
+    /// smtp password, required unless an OAuth2 token is given
     #[clap(long)]
-    smtp_password: String,
+    smtp_password: Option<String>,
+
+    /// OAuth2 bearer token to log in to the smtp server with XOAUTH2 instead of a password,
+    /// e.g. for Microsoft 365
+    #[clap(long, env = "SMTP_OAUTH_TOKEN", hide_env_values = true)]
+    smtp_oauth_token: Option<String>,
+
+    /// Another smtp relay to send the emails with if the smtp server can't be reached,
+    /// e.g. "smtp2.example.org:587" or "user:password@smtp2.example.org".
+    /// Without credentials of its own, it uses those of the smtp server.
+    /// Can be given multiple times, the relays are tried in order.
+    #[clap(long, value_name = "[USER:PASSWORD@]SERVER[:PORT]")]
+    smtp_fallback: Vec<SmtpRelay>,
+
+    /// E-Mail Address the emails are sent from.
+    /// Uses the smtp username by default.
+    #[clap(long)]
+    from_address: Option<String>,
+
+    /// Display name the emails are sent from
+    #[clap(long, default_value = "ElStatus")]
+    from_name: String,
+
+    /// E-Mail Address replies should go to, e.g. the facilities team
+    #[clap(long)]
+    reply_to: Option<String>,
+
+    /// E-Mail Address to send a copy of every email to.
+    /// Can be given multiple times.
+    #[clap(long)]
+    cc: Vec<String>,
+
+    /// Attach the image shown on the main e-paper tag to the status email
+    #[clap(long)]
+    attach_image: bool,
+
+    /// Register an additional template, or replace a default one, e.g. "all_clear.txt=./all_clear.txt".
+    /// Can be given multiple times.
+    #[clap(long = "template", value_name = "NAME=PATH")]
+    templates: Vec<String>,
+
+    /// Only send the status email if an elevator is broken or the status changed since the last one.
+    /// The errors email is sent regardless.
+    #[clap(long)]
+    only_on_issues: bool,
 }
 
-pub fn send_result(
-    equipments: &Vec<Equipment>,
-    errors: &Vec<Box<dyn Error>>,
-    tera: &Tera,
-    args: &EMailArgs,
-) {
-    let num_ok = equipments
-        .iter()
-        .filter(|eq| eq.working.unwrap_or_default())
-        .count();
+/// An smtp relay given with `--smtp-fallback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpRelay {
+    pub server: String,
+    /// The default port of the relay if not given
+    pub port: Option<u16>,
+    /// The user and password, those of `--smtp-server` if not given
+    pub credentials: Option<(String, String)>,
+}
 
-    let num_failed = equipments
-        .iter()
-        .filter(|eq| !eq.working.unwrap_or(true))
-        .count();
+impl FromStr for SmtpRelay {
+    type Err = String;
+
+    /// Parses "[USER:PASSWORD@]SERVER[:PORT]". The user may be an email address,
+    /// so the credentials end at the last "@".
+    fn from_str(relay: &str) -> Result<Self, Self::Err> {
+        let (credentials, address) = match relay.rsplit_once('@') {
+            Some((credentials, address)) => {
+                let (user, password) = credentials.split_once(':').ok_or_else(|| {
+                    format!("Missing password in {relay}, expected USER:PASSWORD@SERVER")
+                })?;
+                (Some((user.to_owned(), password.to_owned())), address)
+            }
+            None => (None, relay),
+        };
+        let (server, port) = match address.split_once(':') {
+            Some((server, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|err| format!("Invalid port {port} in {relay}: {err}"))?;
+                (server, Some(port))
+            }
+            None => (address, None),
+        };
+        if server.is_empty() {
+            return Err(format!("Missing server in {relay}"));
+        }
+        Ok(Self {
+            server: server.to_owned(),
+            port,
+            credentials,
+        })
+    }
+}
 
-    let num_unknown = equipments.iter().filter(|eq| eq.working.is_none()).count() + errors.len();
+/// The server and port, without the credentials, for log messages.
+impl Display for SmtpRelay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.server)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The equipments of the last status email, for `--only-on-issues` and the changes since.
+const STATE_FILE: &str = "elstatus.email.json";
+
+/// The equipments of the last status email, if known.
+pub fn load_state(data_dir: &DataDir) -> Option<Vec<Equipment>> {
+    let equipments_json = std::fs::read_to_string(data_dir.state_file(STATE_FILE)).ok()?;
+    serde_json::from_str(&equipments_json).ok()
+}
+
+fn store_state(data_dir: &DataDir, equipments: &[Equipment]) -> Result<(), Box<dyn Error>> {
+    std::fs::write(
+        data_dir.state_file(STATE_FILE),
+        serde_json::to_string(equipments)?,
+    )?;
+    Ok(())
+}
+
+impl EMailArgs {
+    /// Everything that is wrong with the arguments, found without connecting to the smtp server:
+    /// addresses that don't parse and templates that are missing or don't compile.
+    pub fn config_problems(&self) -> Vec<String> {
+        let mut results = vec![
+            sender(self).map(drop),
+            parse_mailbox("status", &self.status_address).map(drop),
+            parse_mailbox("errors", &self.errors_address).map(drop),
+        ];
+        if let Some(reply_to) = &self.reply_to {
+            results.push(parse_mailbox("reply-to", reply_to).map(drop));
+        }
+        for cc in self.cc.iter() {
+            results.push(parse_mailbox("cc", cc).map(drop));
+        }
+        if self.smtp_password.is_none() && self.smtp_oauth_token.is_none() {
+            results.push(Err(
+                "Either an smtp password or an OAuth2 token is required".into(),
+            ));
+        }
+
+        // Tera stops at the first template it can't load, so report all missing files first
+        let missing: Vec<_> = self
+            .templates
+            .iter()
+            .filter_map(|template| template.split_once('='))
+            .filter(|(_, path)| !Path::new(path).is_file())
+            .map(|(name, path)| Err(format!("Template {} not found at {}", name, path).into()))
+            .collect();
+        if missing.is_empty() {
+            results.push(templates(self).map(drop));
+        } else {
+            results.extend(missing);
+        }
+
+        results
+            .into_iter()
+            .filter_map(Result::err)
+            .map(|err| error_chain(err.as_ref()))
+            .collect()
+    }
+}
+
+/// The error followed by its sources, Tera only names the broken template in the outermost error.
+pub(crate) fn error_chain(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message = format!("{}: {}", message, err);
+        source = err.source();
+    }
+    message
+}
+
+/// Whether the status email is worth sending with `--only-on-issues`:
+/// something is broken, unknown or couldn't be checked, or the status changed since the `previous` email.
+pub fn has_issues(
+    summary: &StatusSummary,
+    previous: Option<&[Equipment]>,
+    equipments: &[Equipment],
+) -> bool {
+    let all_clear = summary.exit_code() == 0
+        && summary.num_minor_failed == 0
+        && summary.num_maintenance == 0
+        && summary.num_recently_broken == 0;
+    !all_clear || previous != Some(equipments)
+}
+
+fn parse_mailbox(kind: &str, address: &str) -> Result<Mailbox, Box<dyn Error>> {
+    Ok(address
+        .parse()
+        .map_err(|err| format!("Invalid {} address {}: {}", kind, address, err))?)
+}
+
+/// The sender of all emails, e.g. "ElStatus <elstatus@example.org>".
+fn sender(args: &EMailArgs) -> Result<Mailbox, Box<dyn Error>> {
+    let address = args.from_address.as_ref().unwrap_or(&args.smtp_user);
+    let address = address
+        .parse()
+        .map_err(|err| format!("Invalid from address {}: {}", address, err))?;
+    Ok(Mailbox::new(Some(args.from_name.clone()), address))
+}
+
+/// A message to `to` with the sender, Reply-To and CC headers set up from `args`.
+fn message_to(to: &str, args: &EMailArgs) -> Result<MessageBuilder, Box<dyn Error>> {
+    let mut message = Message::builder()
+        .from(sender(args)?)
+        .to(parse_mailbox("to", to)?);
+    if let Some(reply_to) = &args.reply_to {
+        message = message.reply_to(parse_mailbox("reply-to", reply_to)?);
+    }
+    for cc in args.cc.iter() {
+        message = message.cc(parse_mailbox("cc", cc)?);
+    }
+    Ok(message)
+}
+
+/// The Content-ID of the attached e-paper image, templates can show it with `<img src="cid:...">`.
+const IMAGE_CONTENT_ID: &str = "elstatus";
+
+/// Templates used instead of status.txt and status.html when every elevator works, if registered.
+const ALL_CLEAR_TEMPLATE: &str = "all_clear";
+
+/// The built-in status.html, status.txt and errors.txt templates.
+pub fn default_templates() -> tera::Result<Tera> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(vec![
+        ("status.html", include_str!("templates/status.html")),
+        ("status.txt", include_str!("templates/status.txt")),
+        ("errors.txt", include_str!("templates/errors.txt")),
+    ])?;
+    Ok(tera)
+}
+
+/// The default templates, plus the ones given with `--template`.
+pub fn templates(args: &EMailArgs) -> Result<Tera, Box<dyn Error>> {
+    let mut tera = default_templates()?;
+
+    let extra_templates = args
+        .templates
+        .iter()
+        .map(|template| {
+            template
+                .split_once('=')
+                .map(|(name, path)| (path, Some(name)))
+                .ok_or_else(|| format!("Invalid template, expected \"NAME=PATH\": {}", template))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tera.add_template_files(extra_templates)?;
+    Ok(tera)
+}
 
-    let ok_status = if num_ok > 0 { "✅" } else { "" };
-    let failed_status = if num_failed > 0 { "⛔" } else { "" };
-    let unknown_status = if num_unknown > 0 { "❔" } else { "" };
+/// The name of the status template with the given extension that fits the summary.
+fn status_template(tera: &Tera, summary: &StatusSummary, extension: &str) -> String {
+    let all_clear = format!("{}.{}", ALL_CLEAR_TEMPLATE, extension);
+    if summary.exit_code() == 0 && tera.get_template_names().any(|name| name == all_clear) {
+        all_clear
+    } else {
+        format!("status.{}", extension)
+    }
+}
 
-    let status_message = match (num_failed, num_ok, num_unknown) {
-        (0, _, _) if num_ok > 0 && num_unknown > 0 => "Kein defekter Aufzug (einige Unbekannt)!",
-        (0, 0, _) => "Warnung: Aufzugstatus unbekannt!",
-        (0, _, 0) => "Alle Aufzüge funktionieren!",
-        (_, _, _) => "Achtung: Defekter Aufzug auf dem Weg!",
+/// The context the status templates are rendered with.
+/// `image_cid` is only set if `with_image` is, see [`send_result`].
+/// The `changes` since the last status email are listed as `newly_broken` and `back_in_service`.
+/// Only the `max_listed` most important broken elevators are listed, see [`limit_broken`],
+/// the number of the others is `more_broken`.
+/// The `run` the email is sent by is available as `run`, see [`errors_context`].
+pub fn status_context(
+    equipments: &[Equipment],
+    errors: &[Box<dyn Error>],
+    summary: &StatusSummary,
+    changes: &[(StatusChange, &Equipment)],
+    max_listed: Option<usize>,
+    run: Option<&RunContext>,
+    with_image: bool,
+) -> tera::Context {
+    let mut context = errors_context(errors, run);
+    let (equipments, more_broken) = limit_broken(equipments, max_listed);
+    context.insert("image_cid", &with_image.then_some(IMAGE_CONTENT_ID));
+    context.insert("equipments", &equipments);
+    context.insert("stations", &group_by_station(&equipments));
+    context.insert("more_broken", &more_broken);
+    context.insert("summary", summary);
+    let changed = |kind| -> Vec<_> {
+        changes
+            .iter()
+            .filter(|(change, _)| *change == kind)
+            .map(|(_, equipment)| equipment)
+            .collect()
     };
+    context.insert("newly_broken", &changed(StatusChange::NewlyBroken));
+    context.insert("back_in_service", &changed(StatusChange::BackInService));
+    context
+}
+
+/// The subject of the status email: the headline of the `summary`,
+/// led by whether more elevators broke down or were repaired since the last status email.
+pub fn subject(summary: &StatusSummary, changes: &[(StatusChange, &Equipment)]) -> String {
+    let count = |kind| changes.iter().filter(|(change, _)| *change == kind).count();
+    let newly_broken = count(StatusChange::NewlyBroken);
+    let back_in_service = count(StatusChange::BackInService);
+    let headline = summary.headline();
+    match newly_broken.cmp(&back_in_service) {
+        Ordering::Less => format!("🎉 {back_in_service} wieder in Betrieb - {headline}"),
+        Ordering::Greater => format!("📉 {newly_broken} neu defekt - {headline}"),
+        Ordering::Equal if newly_broken == 0 => headline,
+        Ordering::Equal => format!(
+            "🔀 {newly_broken} neu defekt, {back_in_service} wieder in Betrieb - {headline}"
+        ),
+    }
+}
 
+/// The context the errors template is rendered with.
+/// `failed_stations` lists the stations that couldn't be fetched at all, see [`crate::Error::for_station`].
+/// If a `run` is given, the templates name it in their footer.
+pub fn errors_context(errors: &[Box<dyn Error>], run: Option<&RunContext>) -> tera::Context {
     let mut context = tera::Context::new();
-    context.insert("equipments", equipments);
+    context.insert("run", &run);
     context.insert(
         "errors",
         &errors.iter().map(|err| err.to_string()).collect::<Vec<_>>(),
     );
+    let failed_stations: Vec<_> = errors
+        .iter()
+        .filter_map(|err| match err.downcast_ref() {
+            Some(crate::Error::Station { station, .. }) => Some(station),
+            _ => None,
+        })
+        .collect();
+    context.insert("failed_stations", &failed_stations);
+    context
+}
+
+/// The file name of the structured errors attached to the errors email.
+const ERRORS_ATTACHMENT: &str = "errors.json";
+
+/// The `errors` as a JSON array of [`crate::StructuredError`]s, as attached to the errors email.
+pub fn structured_errors_json(errors: &[Box<dyn Error>]) -> serde_json::Result<String> {
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|err| structured_error(err.as_ref()))
+        .collect();
+    serde_json::to_string_pretty(&errors)
+}
+
+/// Rejected credentials, 5xx replies and malformed messages won't go away by trying again.
+/// Connection problems and 4xx replies might.
+fn is_retryable(err: &smtp::Error) -> bool {
+    !(err.is_permanent() || err.is_client() || err.is_response() || err.is_tls())
+}
+
+/// Whether sending failed before the relay could answer, e.g. because it is down or unreachable,
+/// so another relay might still succeed.
+fn is_connection_failure(err: &smtp::Error) -> bool {
+    !(err.is_permanent() || err.is_transient() || err.is_client() || err.is_response())
+}
+
+/// The transport for the smtp relay at `server`, on its default port unless a `port` is given.
+/// Logs in with XOAUTH2 if an `oauth_token` is given, with the password otherwise.
+fn transport(
+    server: &str,
+    port: Option<u16>,
+    user: &str,
+    password: Option<&str>,
+    oauth_token: Option<&str>,
+) -> Result<SmtpTransport, Box<dyn Error>> {
+    let mut relay = SmtpTransport::relay(server)?;
+    if let Some(port) = port {
+        relay = relay.port(port);
+    }
+    let relay = match (oauth_token, password) {
+        (Some(token), _) => relay
+            .credentials(Credentials::new(user.to_owned(), token.to_owned()))
+            .authentication(vec![Mechanism::Xoauth2]),
+        (None, Some(password)) => {
+            relay.credentials(Credentials::new(user.to_owned(), password.to_owned()))
+        }
+        (None, None) => return Err("Either an smtp password or an OAuth2 token is required".into()),
+    };
+    Ok(relay.build())
+}
+
+/// The transport for the smtp server or one of the `--smtp-fallback` relays, see [`transport`].
+fn relay_transport(relay: &SmtpRelay, args: &EMailArgs) -> Result<SmtpTransport, Box<dyn Error>> {
+    match &relay.credentials {
+        Some((user, password)) => transport(&relay.server, relay.port, user, Some(password), None),
+        None => transport(
+            &relay.server,
+            relay.port,
+            &args.smtp_user,
+            args.smtp_password.as_deref(),
+            args.smtp_oauth_token.as_deref(),
+        ),
+    }
+}
+
+/// Send `email` via the SMTP relay, retrying transient failures with exponential backoff.
+/// If the relay can't be reached at all, the `--smtp-fallback` relays are tried in order,
+/// so only if none of them can be reached is it an error.
+fn send(email: &Message, args: &EMailArgs) -> Result<(), Box<dyn Error>> {
+    let primary = SmtpRelay {
+        server: args.smtp_server.clone(),
+        port: None,
+        credentials: None,
+    };
+    let relays: Vec<_> = std::iter::once(&primary)
+        .chain(args.smtp_fallback.iter())
+        .collect();
+
+    const NUM_ATTEMPTS: u32 = 5;
+    let mut failures = Vec::new();
+    for (i, relay) in relays.iter().enumerate() {
+        let mailer = relay_transport(relay, args)?;
+        match retry_with_backoff_if(NUM_ATTEMPTS, Duration::from_secs(1), is_retryable, || {
+            mailer.send(email)
+        }) {
+            Ok(_) => return Ok(()),
+            Err(err) if is_connection_failure(&err) => {
+                if let Some(next) = relays.get(i + 1) {
+                    println!("⚠️ Could not reach smtp relay {relay}: {err} - trying {next}");
+                }
+                failures.push(format!("{relay}: {err}"));
+            }
+            Err(err) => return Err(format!("Could not send email via {relay}: {err}").into()),
+        }
+    }
+    Err(format!("Could not send email: {}", failures.join("; ")).into())
+}
+
+/// Send the status email, rendered with the `context` from [`status_context`].
+/// The subject and the template come from `summary`,
+/// which may count unknown elevators differently, see [`StatusSummary::with_unknown_as`].
+/// If an `image` is given, it is attached inline, the `context` must have been created `with_image` then.
+/// The `changes` since the last status email lead the subject, see [`subject`].
+pub fn send_result(
+    context: &tera::Context,
+    summary: &StatusSummary,
+    changes: &[(StatusChange, &Equipment)],
+    image: Option<&RgbImage>,
+    tera: &Tera,
+    args: &EMailArgs,
+) -> Result<(), Box<dyn Error>> {
     let html_message = tera
-        .render("status.html", &context)
+        .render(&status_template(tera, summary, "html"), context)
         .unwrap_or_else(|err| format!("Error while creating message: {}", err));
     let text_message = tera
-        .render("status.txt", &context)
+        .render(&status_template(tera, summary, "txt"), context)
         .unwrap_or_else(|err| format!("Error while creating message: {}", err));
 
-    let email = Message::builder()
-        .from(format!("ElStatus <{}>", args.smtp_user).parse().unwrap())
-        .to(args.status_address.parse().unwrap())
-        .subject(format!(
-            "{}{}{} {}",
-            failed_status, ok_status, unknown_status, status_message
-        ))
-        .multipart(MultiPart::alternative_plain_html(
-            text_message,
-            html_message,
-        ))
-        .unwrap();
-
-    let creds = Credentials::new(args.smtp_user.clone(), args.smtp_password.clone());
+    let email = message_to(&args.status_address, args)?
+        .subject(subject(summary, changes))
+        .multipart(match image {
+            Some(image) => {
+                let mut png = Vec::new();
+                image.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+                MultiPart::alternative()
+                    .singlepart(SinglePart::plain(text_message))
+                    .multipart(
+                        MultiPart::related()
+                            .singlepart(SinglePart::html(html_message))
+                            .singlepart(
+                                Attachment::new_inline(IMAGE_CONTENT_ID.to_owned())
+                                    .body(png, ContentType::parse("image/png")?),
+                            ),
+                    )
+            }
+            None => MultiPart::alternative_plain_html(text_message, html_message),
+        })?;
 
-    let mailer = SmtpTransport::relay(&args.smtp_server)
-        .unwrap()
-        .credentials(creds)
-        .build();
-
-    // Send the email
-    match mailer.send(&email) {
-        Ok(_) => println!("Status email sent successfully!"),
-        Err(e) => panic!("Could not send email: {:?}", e),
-    }
+    send(&email, args)?;
+    println!("Status email sent successfully!");
+    Ok(())
 }
 
-pub fn send_errors(errors: &Vec<Box<dyn Error>>, tera: &Tera, args: &EMailArgs) {
+/// Send the errors email: the errors.txt template for humans,
+/// with the errors as [`crate::StructuredError`]s attached as errors.json.
+pub fn send_errors(
+    errors: &[Box<dyn Error>],
+    run: &RunContext,
+    tera: &Tera,
+    args: &EMailArgs,
+) -> Result<(), Box<dyn Error>> {
     if errors.is_empty() {
-        return;
+        return Ok(());
     }
 
-    let mut context = tera::Context::new();
-    context.insert(
-        "errors",
-        &errors.iter().map(|err| err.to_string()).collect::<Vec<_>>(),
-    );
     let text_message = tera
-        .render("errors.txt", &context)
-        .unwrap_or_else(|err| format!("Error while creating message: {}", err.to_string()));
+        .render("errors.txt", &errors_context(errors, Some(run)))
+        .unwrap_or_else(|err| format!("Error while creating message: {}", err));
 
-    let email = Message::builder()
-        .from(format!("ElStatus <{}>", args.smtp_user).parse().unwrap())
-        .to(args.errors_address.parse().unwrap())
+    let email = message_to(&args.errors_address, args)?
         .subject(format!(
             "{} Errors encountered when checking elevator status",
             errors.len()
         ))
-        .body(text_message)
-        .unwrap();
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(text_message))
+                .singlepart(Attachment::new(ERRORS_ATTACHMENT.to_owned()).body(
+                    structured_errors_json(errors)?,
+                    ContentType::parse("application/json")?,
+                )),
+        )?;
+
+    send(&email, args)?;
+    println!("Errors E-Mail sent successfully!");
+    Ok(())
+}
 
-    let creds = Credentials::new(args.smtp_user.clone(), args.smtp_password.clone());
+/// Sends the status email, and the errors email if there were any errors.
+pub struct EMailNotifier<'a> {
+    pub args: &'a EMailArgs,
+    /// See [`status_context`]
+    pub max_listed: Option<usize>,
+    /// The images the displays were updated with in this run, if any.
+    /// The main one is attached with `--attach-image`, so it is exactly what the tags show.
+    pub displayed: Option<&'a display::UpdatedImages>,
+    /// Renders the attached image if the displays weren't updated, configured like the displays,
+    /// see [`display::DisplayArgs::renderer`]
+    pub renderer: &'a display::DisplayRenderer,
+    /// Named in the footer of the emails
+    pub run: &'a RunContext,
+    /// Where the last status email is remembered for `--only-on-issues`
+    pub data_dir: &'a DataDir,
+}
+
+impl Notifier for EMailNotifier<'_> {
+    fn name(&self) -> &str {
+        "E-Mail"
+    }
+
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        let tera = templates(self.args)?;
+        let previous = load_state(self.data_dir);
+        if self.args.only_on_issues && !has_issues(summary, previous.as_deref(), equipments) {
+            println!("📭 Nothing broken and nothing changed, not sending the status email");
+        } else {
+            let image = self.args.attach_image.then(|| {
+                self.displayed
+                    .and_then(|displayed| displayed.borrow().as_ref().map(|(main, _)| main.clone()))
+                    .unwrap_or_else(|| self.renderer.render(equipments, &self.run.started_at).0)
+            });
+            let changes = previous
+                .as_deref()
+                .map(|previous| status_changes(previous, equipments))
+                .unwrap_or_default();
+            let context = status_context(
+                equipments,
+                errors,
+                summary,
+                &changes,
+                self.max_listed,
+                Some(self.run),
+                image.is_some(),
+            );
+            send_result(
+                &context,
+                summary,
+                &changes,
+                image.as_ref(),
+                &tera,
+                self.args,
+            )?;
+            if let Err(err) = store_state(self.data_dir, equipments) {
+                println!("Could not store the status of the email: {}", err);
+            }
+        }
+        send_errors(errors, self.run, &tera, self.args)
+    }
+}
 
-    let mailer = SmtpTransport::relay(&*args.smtp_server)
-        .unwrap()
-        .credentials(creds)
-        .build();
+/// Connect and log in to the SMTP relay without sending anything.
+/// See [`transport`] for `password` and `oauth_token`.
+pub fn check_connection(
+    server: &str,
+    user: &str,
+    password: Option<&str>,
+    oauth_token: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let mailer = transport(server, None, user, password, oauth_token)?;
 
-    // Send the email
-    match mailer.send(&email) {
-        Ok(_) => println!("Errors E-Mail sent successfully!"),
-        Err(e) => panic!("Could not send email: {:?}", e),
+    if mailer.test_connection()? {
+        Ok(())
+    } else {
+        Err(format!("SMTP server {} did not respond", server).into())
     }
 }
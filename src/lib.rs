@@ -1,170 +1,1878 @@
+use cache::ResponseCache;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use data_dir::DataDir;
+use ngrammatic::{CorpusBuilder, Pad};
+use rate_limit::RateLimiter;
+use reqwest::{
+    blocking::Response,
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    StatusCode,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::error::Error;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
 
+pub mod cache;
+pub mod change;
+pub mod config;
+pub mod data_dir;
+pub mod debounce;
 pub mod display;
 pub mod email;
+pub mod matrix;
+pub mod metrics;
+pub mod notifier;
+pub mod quiet;
+pub mod rate_limit;
+pub mod report;
+pub mod run;
+pub mod summary;
+pub mod test_notification;
+pub mod watch;
+pub mod webhook;
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum EquipmentAccessError {
+    #[error("Missing value: {0} in JSON: {1}")]
     MissingValue(String, String),
-    InvalidType {
-        expected_type: String,
-        json: String,
-    },
+    #[error("Expected JSON: {json} to be of type: {expected_type}")]
+    InvalidType { expected_type: String, json: String },
+    #[error("HTTP request failed, error code: {}\n{response_text}", .status.as_str())]
     HTTPRequestError {
         status: reqwest::StatusCode,
         response_text: String,
     },
-    CannotFindEquipment {
-        query_text: String,
+    /// The API responded with 401 or 403
+    #[error("The API rejected the app token (error code: {}), check your WHEELMAP_TOKEN", .status.as_str())]
+    Unauthorized { status: reqwest::StatusCode },
+    /// The API still responded with 429 after retrying
+    #[error(
+        "You are being rate limited by the API, try again later or lower --max-requests-per-second"
+    )]
+    RateLimited,
+    #[error("Could not find elevator: {query_text}")]
+    CannotFindEquipment { query_text: String },
+    #[error("Request to {host} timed out after {} seconds", .timeout.as_secs_f32())]
+    Timeout { host: String, timeout: Duration },
+    #[error("No elevators found near {latitude},{longitude}")]
+    NoEquipmentFound { latitude: f32, longitude: f32 },
+    #[error("No elevators found at place {place_id}")]
+    NoEquipmentAtPlace { place_id: String },
+    #[error("No elevators found in {}", .path.display())]
+    NoEquipmentInFile { path: PathBuf },
+    /// The response was larger than allowed, see [`ApiClient::with_max_response_size`]
+    #[error("The response is larger than {max_size} bytes, see --max-response-size")]
+    ResponseTooLarge { max_size: u64 },
+    /// The response isn't valid UTF-8, e.g. because a proxy responded with something else than JSON
+    #[error("The response of type {content_type} is not valid UTF-8, it starts with: {preview}")]
+    InvalidEncoding {
+        content_type: String,
+        /// The first bytes of the response in hex
+        preview: String,
     },
 }
 
-impl std::fmt::Display for EquipmentAccessError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Everything that can go wrong when fetching equipments from the API.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Reading the body of a response failed after it started
+    #[error("Could not read the response: {0}")]
+    ResponseBody(std::io::Error),
+    #[error("WHEELMAP_TOKEN: {0}")]
+    Env(#[from] std::env::VarError),
+    #[error("Could not read token file {}: {source}", .path.display())]
+    TokenFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file of a [`Location::File`] couldn't be read
+    #[error("Could not read equipment file {}: {source}", .path.display())]
+    EquipmentFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Access(#[from] EquipmentAccessError),
+    /// Some features of the response couldn't be parsed
+    #[error("Errors encountered when sourcing equipments:{}", .0.iter().map(|err| format!("\n{}", err)).collect::<String>())]
+    InvalidEquipments(Vec<EquipmentAccessError>),
+    /// Fetching the equipments of a station failed, see [`Error::for_station`]
+    #[error("Failed to fetch {station}: {source}")]
+    Station { station: String, source: Box<Error> },
+    /// [`EquipmentListBuilder::build`] was called without setting a required field
+    #[error("Equipment list is missing its {0}")]
+    IncompleteEquipmentList(&'static str),
+    /// The coordinates of a location are out of range, see [`Location::validate`]
+    #[error("Invalid coordinates {latitude},{longitude}: {problem}")]
+    InvalidCoordinates {
+        latitude: f32,
+        longitude: f32,
+        problem: String,
+    },
+}
+
+impl Error {
+    /// Attach the station of `list` to the error, so it tells which station couldn't be fetched.
+    pub fn for_station(self, list: &EquipmentList) -> Self {
+        Error::Station {
+            station: list.name(),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// An error as machine-readable data, e.g. for automated triage, see [`structured_error`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StructuredError {
+    /// The variant of [`EquipmentAccessError`] or [`Error`], e.g. "CannotFindEquipment", or "Other"
+    pub kind: String,
+    /// The message for humans, as in the errors email
+    pub message: String,
+    /// The station that couldn't be fetched, see [`Error::for_station`]
+    pub station: Option<String>,
+    /// The HTTP status code the API responded with
+    pub status: Option<u16>,
+    /// The search that couldn't be matched
+    pub query: Option<String>,
+    /// The JSON that couldn't be parsed
+    pub json: Option<String>,
+}
+
+impl StructuredError {
+    fn new(kind: &str, err: &dyn std::error::Error) -> Self {
+        Self {
+            kind: kind.to_owned(),
+            message: err.to_string(),
+            station: None,
+            status: None,
+            query: None,
+            json: None,
+        }
+    }
+}
+
+impl EquipmentAccessError {
+    /// The variant and the details of this error as data
+    pub fn to_structured(&self) -> StructuredError {
+        let kind = match self {
+            Self::MissingValue(..) => "MissingValue",
+            Self::InvalidType { .. } => "InvalidType",
+            Self::HTTPRequestError { .. } => "HTTPRequestError",
+            Self::Unauthorized { .. } => "Unauthorized",
+            Self::RateLimited => "RateLimited",
+            Self::CannotFindEquipment { .. } => "CannotFindEquipment",
+            Self::Timeout { .. } => "Timeout",
+            Self::NoEquipmentFound { .. } => "NoEquipmentFound",
+            Self::NoEquipmentAtPlace { .. } => "NoEquipmentAtPlace",
+            Self::NoEquipmentInFile { .. } => "NoEquipmentInFile",
+            Self::ResponseTooLarge { .. } => "ResponseTooLarge",
+            Self::InvalidEncoding { .. } => "InvalidEncoding",
+        };
+        let mut structured = StructuredError::new(kind, self);
         match self {
-            EquipmentAccessError::MissingValue(value, json) => {
-                write!(f, "Missing value: {} in JSON: {}", value, json)
+            Self::MissingValue(_, json) | Self::InvalidType { json, .. } => {
+                structured.json = Some(json.clone())
             }
-            EquipmentAccessError::InvalidType {
-                expected_type,
-                json,
-            } => {
-                write!(
-                    f,
-                    "Expected JSON: {} to be of type: {}",
-                    json, expected_type
-                )
+            Self::HTTPRequestError { status, .. } | Self::Unauthorized { status } => {
+                structured.status = Some(status.as_u16())
             }
-            EquipmentAccessError::HTTPRequestError {
-                status,
-                response_text,
-            } => {
-                write!(
-                    f,
-                    "HTTP request failed, error code: {}\n{}",
-                    status.as_str(),
-                    response_text
-                )
+            Self::RateLimited => structured.status = Some(StatusCode::TOO_MANY_REQUESTS.as_u16()),
+            Self::CannotFindEquipment { query_text } => structured.query = Some(query_text.clone()),
+            Self::Timeout { .. }
+            | Self::NoEquipmentFound { .. }
+            | Self::NoEquipmentAtPlace { .. }
+            | Self::NoEquipmentInFile { .. }
+            | Self::ResponseTooLarge { .. }
+            | Self::InvalidEncoding { .. } => {}
+        }
+        structured
+    }
+}
+
+impl Error {
+    /// The variant and the details of this error as data.
+    /// API errors keep the variant of their [`EquipmentAccessError`].
+    pub fn to_structured(&self) -> StructuredError {
+        match self {
+            Error::Access(err) => err.to_structured(),
+            Error::Station { station, source } => StructuredError {
+                message: self.to_string(),
+                station: Some(station.clone()),
+                ..source.to_structured()
+            },
+            Error::Request(err) => StructuredError {
+                status: err.status().map(|status| status.as_u16()),
+                ..StructuredError::new("Request", self)
+            },
+            Error::Json(_) => StructuredError::new("Json", self),
+            Error::ResponseBody(_) => StructuredError::new("ResponseBody", self),
+            Error::Env(_) => StructuredError::new("Env", self),
+            Error::TokenFile { .. } => StructuredError::new("TokenFile", self),
+            Error::EquipmentFile { .. } => StructuredError::new("EquipmentFile", self),
+            Error::InvalidEquipments(_) => StructuredError::new("InvalidEquipments", self),
+            Error::IncompleteEquipmentList(_) => {
+                StructuredError::new("IncompleteEquipmentList", self)
             }
-            EquipmentAccessError::CannotFindEquipment { query_text } => {
-                write!(f, "Could not find elevator: {}", query_text)
+            Error::InvalidCoordinates { .. } => StructuredError::new("InvalidCoordinates", self),
+        }
+    }
+}
+
+/// Any error as [`StructuredError`]. Errors other than [`Error`] and [`EquipmentAccessError`]
+/// only have their message, with the kind "Other".
+pub fn structured_error(err: &(dyn std::error::Error + 'static)) -> StructuredError {
+    if let Some(err) = err.downcast_ref::<Error>() {
+        err.to_structured()
+    } else if let Some(err) = err.downcast_ref::<EquipmentAccessError>() {
+        err.to_structured()
+    } else {
+        StructuredError::new("Other", err)
+    }
+}
+
+/// Report a timed out request as [`EquipmentAccessError::Timeout`],
+/// so it can be told apart from other failures.
+pub(crate) fn request_error(err: reqwest::Error, timeout: Duration) -> Error {
+    if err.is_timeout() {
+        EquipmentAccessError::Timeout {
+            host: err
+                .url()
+                .and_then(|url| url.host_str())
+                .unwrap_or("unknown host")
+                .to_owned(),
+            timeout,
+        }
+        .into()
+    } else {
+        err.into()
+    }
+}
+
+/// Call `attempt` until it succeeds, at most `num_attempts` times.
+/// The delay between attempts starts at `delay` and doubles after every failure.
+pub fn retry_with_backoff<T, E: std::fmt::Display>(
+    num_attempts: u32,
+    delay: Duration,
+    attempt: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    retry_with_backoff_if(num_attempts, delay, |_| true, attempt)
+}
+
+/// Like [`retry_with_backoff`], but gives up right away on errors for which `is_retryable` is false.
+pub fn retry_with_backoff_if<T, E: std::fmt::Display>(
+    num_attempts: u32,
+    mut delay: Duration,
+    is_retryable: impl Fn(&E) -> bool,
+    mut attempt: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut i = 1;
+    loop {
+        match attempt() {
+            Ok(result) => return Ok(result),
+            Err(err) if i >= num_attempts || !is_retryable(&err) => return Err(err),
+            Err(err) => {
+                println!(
+                    "⚠️ {i}/{num_attempts} failed: {err} - ⏳ retrying in {} ms!",
+                    delay.as_millis()
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                i += 1;
             }
         }
     }
 }
 
-impl Error for EquipmentAccessError {}
+/// How important an elevator is, e.g. whether it is the only accessible route.
+/// Set per search in [`EquipmentList::priorities`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Broken elevators are only mentioned as a notice, e.g. if there is another one next to it
+    Low,
+    #[default]
+    Normal,
+    /// Broken elevators raise a critical alert
+    High,
+}
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Equipment {
+    /// The `_id` of the accessibility-cloud feature
+    #[serde(default)]
+    id: Option<String>,
     name: String,
     category: String,
     working: Option<bool>,
     place: Option<String>,
+    /// The label of the [`EquipmentList`] this equipment was found for
+    #[serde(default)]
+    station: Option<String>,
+    /// When the API last saw the working status change
+    #[serde(default)]
+    last_update: Option<DateTime<Utc>>,
+    /// The GeoJSON point of the feature, as `[longitude, latitude]`
+    #[serde(default)]
+    coordinates: Option<[f64; 2]>,
+    /// The priority of the search this equipment was found for
+    #[serde(default)]
+    priority: Priority,
+    /// Whether the equipment is out of service for planned maintenance, rather than unexpectedly broken
+    #[serde(default)]
+    maintenance: bool,
+    /// Whether the equipment has been broken for less than `--broken-min-duration`,
+    /// so it is listed as recently reported broken instead of being alerted about
+    #[serde(default)]
+    recently_broken: bool,
+    /// The complete feature as returned by the API, only kept if requested with `keep_raw`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<Value>,
+}
+
+impl Equipment {
+    /// An equipment with only the fields every source provides, e.g. to build fixtures by hand.
+    /// The optional fields can be filled in with the `with_*` methods.
+    pub fn new(
+        name: impl Into<String>,
+        category: impl Into<String>,
+        working: Option<bool>,
+        place: Option<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            name: name.into(),
+            category: category.into(),
+            working,
+            place,
+            station: None,
+            last_update: None,
+            coordinates: None,
+            priority: Priority::Normal,
+            maintenance: false,
+            recently_broken: false,
+            raw: None,
+        }
+    }
+
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// See [`EquipmentList::station_label`]
+    pub fn with_station(mut self, station: impl Into<String>) -> Self {
+        self.station = Some(station.into());
+        self
+    }
+
+    pub fn with_last_update(mut self, last_update: DateTime<Utc>) -> Self {
+        self.last_update = Some(last_update);
+        self
+    }
+
+    /// `[longitude, latitude]`, as in GeoJSON
+    pub fn with_coordinates(mut self, coordinates: [f64; 2]) -> Self {
+        self.coordinates = Some(coordinates);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// See [`Self::in_maintenance`]
+    pub fn with_maintenance(mut self, maintenance: bool) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// See [`Self::recently_broken`]
+    pub fn with_recently_broken(mut self, recently_broken: bool) -> Self {
+        self.recently_broken = recently_broken;
+        self
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    /// `None` if the API doesn't know whether the equipment works
+    pub fn working(&self) -> Option<bool> {
+        self.working
+    }
+
+    /// The place name reported by the API, see also [`Self::location`]
+    pub fn place(&self) -> Option<&str> {
+        self.place.as_deref()
+    }
+
+    pub fn station(&self) -> Option<&str> {
+        self.station.as_deref()
+    }
+
+    pub fn last_update(&self) -> Option<DateTime<Utc>> {
+        self.last_update
+    }
+
+    pub fn coordinates(&self) -> Option<[f64; 2]> {
+        self.coordinates
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Whether the API marks the equipment as under planned maintenance.
+    /// It isn't counted as broken then, even if it doesn't work.
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance
+    }
+
+    /// Whether the equipment has only been broken for a short time, see `--broken-min-duration`.
+    /// It isn't counted as broken then, unless it has [`Priority::Low`] and is only a notice anyway.
+    pub fn recently_broken(&self) -> bool {
+        self.recently_broken
+    }
+
+    /// The complete GeoJSON feature, e.g. to read properties that aren't modelled here.
+    /// Only available if the [`ApiClient`] was configured to keep it.
+    pub fn raw(&self) -> Option<&Value> {
+        self.raw.as_ref()
+    }
+
+    /// The station label if there is one, otherwise the place name reported by the API.
+    pub fn location(&self) -> Option<&str> {
+        self.station.as_deref().or(self.place.as_deref())
+    }
+}
+
+/// A one-line summary for the logs, e.g. "✅ Gleis 1/2 @ Wannsee (updated 2024-01-05)".
+impl std::fmt::Display for Equipment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let emoji = match self.working {
+            Some(true) => "✅",
+            _ if self.maintenance => "🔧",
+            Some(false) if self.recently_broken => "🕒",
+            Some(false) => "⛔",
+            None => "❔",
+        };
+        write!(f, "{} {}", emoji, self.name)?;
+        if let Some(location) = self.location() {
+            write!(f, " @ {}", location)?;
+        }
+        if let Some(last_update) = self.last_update {
+            write!(f, " (updated {})", last_update.format("%Y-%m-%d"))?;
+        }
+        Ok(())
+    }
+}
+
+/// A search for one elevator of an [`EquipmentList`].
+/// In the list file, it is either the bare query or an object with the optional fields, e.g.
+/// `{ "query": "Gleis 1/2", "alias": "Platform 1/2", "note": "main entrance" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SearchEntry")]
+pub struct EquipmentSearch {
+    /// Matched against the names reported by the API, see [`ApiClient::search_equipments`]
+    pub query: String,
+    /// Shown as the name of the matched equipment instead of the name reported by the API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Only documents the entry, e.g. where the elevator is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchEntry {
+    Query(String),
+    Detailed {
+        query: String,
+        #[serde(default)]
+        alias: Option<String>,
+        #[serde(default)]
+        note: Option<String>,
+    },
+}
+
+impl From<SearchEntry> for EquipmentSearch {
+    fn from(entry: SearchEntry) -> Self {
+        match entry {
+            SearchEntry::Query(query) => query.into(),
+            SearchEntry::Detailed { query, alias, note } => Self { query, alias, note },
+        }
+    }
+}
+
+impl From<String> for EquipmentSearch {
+    fn from(query: String) -> Self {
+        Self {
+            query,
+            alias: None,
+            note: None,
+        }
+    }
+}
+
+impl From<&str> for EquipmentSearch {
+    fn from(query: &str) -> Self {
+        query.to_owned().into()
+    }
+}
+
+/// Where the equipments of an [`EquipmentList`] are fetched from.
+/// In the list, either `latitude` and `longitude`, a `place_id` or a `file` is given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Location {
+    /// All equipment within the search radius around the coordinates, see [`EquipmentList::accuracy_max`]
+    Coordinates { latitude: f32, longitude: f32 },
+    /// The equipment of an accessibility-cloud place, e.g. a station.
+    /// Place ids are stable, so unlike coordinates, no search radius has to be tuned.
+    Place { place_id: String },
+    /// A local file with the same GeoJSON the API would respond with, e.g. for offline demos and tests.
+    /// Its features are parsed and matched just like the API's, but nothing is requested or cached.
+    File { file: PathBuf },
+}
+
+/// Everything that is wrong with the coordinates, e.g. because latitude and longitude were swapped.
+fn coordinate_problems(latitude: f32, longitude: f32) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !(-90.0..=90.0).contains(&latitude) {
+        problems.push(format!("Latitude {} is not between -90 and 90", latitude));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        problems.push(format!(
+            "Longitude {} is not between -180 and 180",
+            longitude
+        ));
+    }
+    if !problems.is_empty()
+        && (-90.0..=90.0).contains(&longitude)
+        && (-180.0..=180.0).contains(&latitude)
+    {
+        problems.push("Latitude and longitude may be swapped".to_owned());
+    }
+    problems
+}
+
+impl Location {
+    /// Fails if the coordinates are out of range, so a typo doesn't silently query the wrong place.
+    /// Place ids are only checked by the API, and files once they are read.
+    pub fn validate(&self) -> Result<(), Error> {
+        let Location::Coordinates {
+            latitude,
+            longitude,
+        } = self
+        else {
+            return Ok(());
+        };
+        let problems = coordinate_problems(*latitude, *longitude);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidCoordinates {
+                latitude: *latitude,
+                longitude: *longitude,
+                problem: problems.join(", "),
+            })
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+/// The coordinates like "52.42,13.18", the place id like "place 4Bm6S2GJbgK5uGPSv",
+/// or the path like "file fixtures/wannsee.json".
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Coordinates {
+                latitude,
+                longitude,
+            } => write!(f, "{latitude},{longitude}"),
+            Location::Place { place_id } => write!(f, "place {place_id}"),
+            Location::File { file } => write!(f, "file {}", file.display()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EquipmentList {
-    pub latitude: f32,
-    pub longitude: f32,
-    pub equipment_searches: Vec<String>,
+    /// Coordinates out of range are only rejected once the list is fetched,
+    /// so `config-check` can report them along with all other problems, see [`Location::validate`]
+    #[serde(flatten)]
+    pub location: Location,
+    pub equipment_searches: Vec<EquipmentSearch>,
+    /// A human-friendly name for the station, e.g. "Berlin-Wannsee".
+    /// Attached to every equipment found for this list.
+    #[serde(default)]
+    pub station_label: Option<String>,
+    /// If set, searches that can't be matched within [`DEFAULT_ACCURACY`] meters are retried
+    /// with a doubled radius until they are matched or this many meters are reached.
+    #[serde(default)]
+    pub accuracy_max: Option<u32>,
+    /// The priority of individual searches, keyed by the query as written in `equipment_searches`.
+    /// Searches that aren't listed have [`Priority::Normal`].
+    #[serde(default)]
+    pub priorities: HashMap<String, Priority>,
+    /// The languages to take the equipment names from, in order of preference, e.g. `["en", "de"]`.
+    /// If empty, [`DEFAULT_LANGUAGES`] are used.
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// How the searches are fuzzy matched against the equipment names
+    #[serde(default)]
+    pub matching: MatchOptions,
+}
+
+/// How searches are fuzzy matched against equipment names, by comparing their n-grams.
+/// The defaults work well for names like "zu Gleis 1/2 (S-Bahn)".
+/// Short names and abbreviations, e.g. "G5" for "Gleis 5", share few n-grams, so they match better with an arity of 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchOptions {
+    /// The length of the n-grams that are compared, 2 by default
+    pub arity: usize,
+    /// Pad the names with `arity - 1` spaces, so their first and last characters weigh more.
+    /// On by default.
+    pub pad: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            arity: 2,
+            pad: true,
+        }
+    }
+}
+
+impl EquipmentList {
+    /// The station label, or the location if there is none.
+    pub fn name(&self) -> String {
+        match &self.station_label {
+            Some(label) => label.clone(),
+            None => self.location.to_string(),
+        }
+    }
+
+    /// Build a list in code, with defaults for everything but the location.
+    pub fn builder() -> EquipmentListBuilder {
+        EquipmentListBuilder::default()
+    }
+
+    /// Everything that is wrong with the list, found without asking the API.
+    pub fn config_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match &self.location {
+            Location::Coordinates {
+                latitude,
+                longitude,
+            } => problems.extend(coordinate_problems(*latitude, *longitude)),
+            Location::Place { place_id } if place_id.trim().is_empty() => {
+                problems.push("Empty place id".to_owned())
+            }
+            Location::Place { .. } => {}
+            Location::File { file } if !file.is_file() => {
+                problems.push(format!("Equipment file {} not found", file.display()))
+            }
+            Location::File { .. } => {}
+        }
+        if self.equipment_searches.is_empty() {
+            problems.push("No equipment searches".to_owned());
+        }
+        if self
+            .equipment_searches
+            .iter()
+            .any(|search| search.query.trim().is_empty())
+        {
+            problems.push("Empty equipment search".to_owned());
+        }
+        for query in self.priorities.keys() {
+            if !self
+                .equipment_searches
+                .iter()
+                .any(|search| search.query == *query)
+            {
+                problems.push(format!("Priority for unknown search \"{}\"", query));
+            }
+        }
+        if self.matching.arity == 0 {
+            problems.push("The matching arity must be at least 1".to_owned());
+        }
+        problems
+    }
+}
+
+/// Builds an [`EquipmentList`], see [`EquipmentList::builder`].
+#[derive(Debug, Default)]
+pub struct EquipmentListBuilder {
+    latitude: Option<f32>,
+    longitude: Option<f32>,
+    place_id: Option<String>,
+    file: Option<PathBuf>,
+    equipment_searches: Vec<EquipmentSearch>,
+    station_label: Option<String>,
+    accuracy_max: Option<u32>,
+    priorities: HashMap<String, Priority>,
+    languages: Vec<String>,
+    matching: MatchOptions,
+}
+
+impl EquipmentListBuilder {
+    pub fn latitude(mut self, latitude: f32) -> Self {
+        self.latitude = Some(latitude);
+        self
+    }
+
+    pub fn longitude(mut self, longitude: f32) -> Self {
+        self.longitude = Some(longitude);
+        self
+    }
+
+    /// Fetch the equipment of this place instead of around the coordinates, see [`Location::Place`]
+    pub fn place_id(mut self, place_id: impl Into<String>) -> Self {
+        self.place_id = Some(place_id.into());
+        self
+    }
+
+    /// Read the equipment from this file instead of asking the API, see [`Location::File`]
+    pub fn file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Add a search, either just the query or an [`EquipmentSearch`] with an alias
+    pub fn add_search(mut self, search: impl Into<EquipmentSearch>) -> Self {
+        self.equipment_searches.push(search.into());
+        self
+    }
+
+    /// Like [`Self::add_search`], with a priority other than [`Priority::Normal`]
+    pub fn add_search_with_priority(
+        mut self,
+        search: impl Into<EquipmentSearch>,
+        priority: Priority,
+    ) -> Self {
+        let search = search.into();
+        self.priorities.insert(search.query.clone(), priority);
+        self.equipment_searches.push(search);
+        self
+    }
+
+    /// See [`EquipmentList::station_label`]
+    pub fn station_label(mut self, station_label: impl Into<String>) -> Self {
+        self.station_label = Some(station_label.into());
+        self
+    }
+
+    /// The maximum search radius in meters, see [`EquipmentList::accuracy_max`]
+    pub fn accuracy(mut self, accuracy_max: u32) -> Self {
+        self.accuracy_max = Some(accuracy_max);
+        self
+    }
+
+    /// Add a language to take the equipment names from, see [`EquipmentList::languages`].
+    /// Languages added first are preferred.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.languages.push(language.into());
+        self
+    }
+
+    /// See [`EquipmentList::matching`]
+    pub fn matching(mut self, matching: MatchOptions) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// Fails if neither a place id, a file nor the latitude and longitude were set,
+    /// or if the coordinates are out of range, see [`Location::validate`].
+    pub fn build(self) -> Result<EquipmentList, Error> {
+        let location = match (self.place_id, self.file) {
+            (Some(place_id), _) => Location::Place { place_id },
+            (None, Some(file)) => Location::File { file },
+            (None, None) => Location::Coordinates {
+                latitude: self
+                    .latitude
+                    .ok_or(Error::IncompleteEquipmentList("latitude"))?,
+                longitude: self
+                    .longitude
+                    .ok_or(Error::IncompleteEquipmentList("longitude"))?,
+            },
+        };
+        location.validate()?;
+        Ok(EquipmentList {
+            location,
+            equipment_searches: self.equipment_searches,
+            station_label: self.station_label,
+            accuracy_max: self.accuracy_max,
+            priorities: self.priorities,
+            languages: self.languages,
+            matching: self.matching,
+        })
+    }
+}
+
+/// The group of equipments without a station label in [`group_by_station`].
+pub const UNGROUPED_STATION: &str = "Ungrouped";
+
+/// Group the equipments by their station label, keeping their order within each station.
+/// Equipments without a label are grouped under [`UNGROUPED_STATION`].
+pub fn group_by_station(equipments: &[Equipment]) -> BTreeMap<&str, Vec<&Equipment>> {
+    let mut stations = BTreeMap::<_, Vec<_>>::new();
+    for equipment in equipments {
+        let station = equipment.station.as_deref().unwrap_or(UNGROUPED_STATION);
+        stations.entry(station).or_default().push(equipment);
+    }
+    stations
+}
+
+/// Remove equipments that were found more than once, e.g. by lists with overlapping radii.
+/// Equipments are identified by their id, or by name, place and coordinates if they have none.
+/// The first occurrence is kept.
+pub fn dedup_equipments(equipments: Vec<Equipment>) -> Vec<Equipment> {
+    let mut seen = HashSet::new();
+    equipments
+        .into_iter()
+        .filter(|equipment| {
+            let key = match &equipment.id {
+                Some(id) => id.clone(),
+                None => format!(
+                    "{}\0{:?}\0{:?}",
+                    equipment.name, equipment.place, equipment.coordinates
+                ),
+            };
+            seen.insert(key)
+        })
+        .collect()
+}
+
+/// Whether `a` and `b` describe the same elevator, possibly as reported by different sources:
+/// either they have the same id, or the same name and place.
+fn same_equipment(a: &Equipment, b: &Equipment) -> bool {
+    matches!((&a.id, &b.id), (Some(a_id), Some(b_id)) if a_id == b_id)
+        || (a.name == b.name && a.place == b.place)
+}
+
+/// Combine the equipments of several sources, e.g. different data providers, into one list.
+///
+/// Equipments describing the same elevator, i.e. with the same id or the same name and place, are merged into one entry:
+///
+/// 1. An entry with a known `working` status wins over one with an unknown status.
+/// 2. Otherwise, the entry with the most recent `last_update` wins.
+///    Entries without a `last_update` are older than any with one.
+/// 3. Otherwise, the entry from the earlier source wins, so the first source is the primary one.
+///
+/// The equipments are returned in the order they were first seen.
+pub fn merge_equipment(sources: Vec<Vec<Equipment>>) -> Vec<Equipment> {
+    let mut merged: Vec<Equipment> = Vec::new();
+    for equipment in sources.into_iter().flatten() {
+        match merged
+            .iter_mut()
+            .find(|existing| same_equipment(existing, &equipment))
+        {
+            Some(existing) => {
+                let precedence =
+                    |equipment: &Equipment| (equipment.working.is_some(), equipment.last_update);
+                if precedence(&equipment) > precedence(existing) {
+                    *existing = equipment;
+                }
+            }
+            None => merged.push(equipment),
+        }
+    }
+    merged
+}
+
+/// A GeoJSON FeatureCollection with a Point feature for each of the `equipments`, e.g. to show
+/// the broken elevators on a map. Its properties are the name, place and working status.
+/// Equipments without coordinates are skipped.
+pub fn to_geojson(equipments: &[Equipment]) -> Value {
+    let features: Vec<_> = equipments
+        .iter()
+        .filter_map(|equipment| {
+            Some(json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": equipment.coordinates? },
+                "properties": {
+                    "name": equipment.name,
+                    "place": equipment.place,
+                    "working": equipment.working,
+                },
+            }))
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// The equipments found for an [`EquipmentList`].
+/// A search that can't be matched doesn't prevent the others from being returned.
+#[derive(Debug, Default)]
+pub struct EquipmentResults {
+    pub equipments: Vec<Equipment>,
+    /// The errors of all searches that couldn't be matched
+    pub errors: Vec<EquipmentAccessError>,
+    /// How well each of the `equipments` matched its search, see [`SearchValidation::score`]
+    pub scores: Vec<f32>,
+}
+
+impl EquipmentResults {
+    /// The queries of the searches that couldn't be matched, see [`EquipmentAccessError::CannotFindEquipment`].
+    pub fn unmatched_queries(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().filter_map(|err| match err {
+            EquipmentAccessError::CannotFindEquipment { query_text } => Some(query_text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// A search of an [`EquipmentList`] that couldn't be matched, e.g. because the elevator was renamed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmatchedSearch {
+    /// The name of the list, see [`EquipmentList::name`]
+    pub station: String,
+    pub query: String,
+}
+
+impl std::fmt::Display for UnmatchedSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" ({})", self.query, self.station)
+    }
+}
+
+/// Every search that couldn't be matched, given the `results` of [`ApiClient::get_all_equipments`] for the `lists`.
+pub fn unmatched_searches(
+    lists: &[EquipmentList],
+    results: &[Result<EquipmentResults, Error>],
+) -> Vec<UnmatchedSearch> {
+    lists
+        .iter()
+        .zip(results)
+        .filter_map(|(list, result)| Some((list, result.as_ref().ok()?)))
+        .flat_map(|(list, results)| {
+            results.unmatched_queries().map(|query| UnmatchedSearch {
+                station: list.name(),
+                query: query.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// A search term together with the equipment it matched.
+pub type SearchResult = (String, Result<Equipment, EquipmentAccessError>);
+
+/// The search radius around the coordinates of an [`EquipmentList`], in meters.
+pub const DEFAULT_ACCURACY: u32 = 500;
+
+/// The public accessibility-cloud API.
+pub const DEFAULT_API_URL: &str = "https://accessibility-cloud.freetls.fastly.net";
+
+/// How often a request to the API is attempted before giving up.
+const NUM_ATTEMPTS: u32 = 3;
+
+/// The first delay after the API rate limited a request without a `Retry-After` header.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(5);
+
+/// The longest the API is waited for when rate limited, even if it asks for longer.
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Percent-encode everything but unreserved characters, so an id stays a single segment of the URL path.
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// The delay given by the `Retry-After` header, either in seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// The largest response of the API that is accepted unless configured otherwise, after decompression.
+pub const DEFAULT_MAX_RESPONSE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// How many bytes of a response that isn't valid UTF-8 are shown in the error.
+const NUM_PREVIEW_BYTES: usize = 32;
+
+/// The query asking the API for the equipment within `accuracy` meters of the coordinates.
+fn coordinates_query(latitude: f32, longitude: f32, accuracy: u32) -> Vec<(&'static str, String)> {
+    vec![
+        ("latitude", latitude.to_string()),
+        ("longitude", longitude.to_string()),
+        ("accuracy", accuracy.to_string()),
+    ]
+}
+
+/// The body of `response` as text, without reading more than `max_size` bytes into memory.
+/// A body that isn't valid UTF-8 is reported with its content type and a preview of its first bytes.
+fn read_body(response: Response, max_size: u64) -> Result<String, Error> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_size)
+    {
+        return Err(EquipmentAccessError::ResponseTooLarge { max_size }.into());
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or("unknown")
+        .to_owned();
+
+    // The content length isn't known for compressed or chunked responses, so read one more byte than allowed
+    let mut body = Vec::new();
+    response
+        .take(max_size + 1)
+        .read_to_end(&mut body)
+        .map_err(Error::ResponseBody)?;
+    if body.len() as u64 > max_size {
+        return Err(EquipmentAccessError::ResponseTooLarge { max_size }.into());
+    }
+
+    String::from_utf8(body).map_err(|err| {
+        let bytes = err.as_bytes();
+        let mut preview: String = bytes
+            .iter()
+            .take(NUM_PREVIEW_BYTES)
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        if bytes.len() > NUM_PREVIEW_BYTES {
+            preview += "…";
+        }
+        EquipmentAccessError::InvalidEncoding {
+            content_type,
+            preview,
+        }
+        .into()
+    })
+}
+
+/// The User-Agent sent to the accessibility-cloud API unless configured otherwise.
+pub const DEFAULT_USER_AGENT: &str = concat!("wheelmap-elstatus/", env!("CARGO_PKG_VERSION"));
+
+fn read_token_file(path: &Path) -> Result<String, Error> {
+    let token = std::fs::read_to_string(path).map_err(|source| Error::TokenFile {
+        path: path.to_owned(),
+        source,
+    })?;
+    Ok(token.trim().to_owned())
+}
+
+/// Read the app token from the file named by WHEELMAP_TOKEN_FILE if set,
+/// otherwise from the WHEELMAP_TOKEN environment variable.
+pub fn app_token_from_env() -> Result<String, Error> {
+    match std::env::var_os("WHEELMAP_TOKEN_FILE") {
+        Some(path) => read_token_file(Path::new(&path)),
+        None => Ok(std::env::var("WHEELMAP_TOKEN")?),
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ApiArgs {
+    /// The accessibility.cloud app token.
+    /// Uses --token-file or the environment (see app_token_from_env) by default.
+    #[clap(long)]
+    app_token: Option<String>,
+
+    /// A file containing the accessibility.cloud app token
+    #[clap(long, value_name = "FILE_PATH")]
+    token_file: Option<PathBuf>,
+
+    /// Base URL of the accessibility-cloud instance to query
+    #[clap(long, default_value = DEFAULT_API_URL)]
+    api_url: String,
+
+    /// User-Agent header sent with every API request
+    #[clap(long, default_value = DEFAULT_USER_AGENT)]
+    user_agent: String,
+
+    /// Timeout for API requests
+    #[clap(long, value_name = "SECONDS", default_value_t = 30)]
+    timeout: u64,
+
+    /// Print the best matching candidates and their scores for every search
+    #[clap(long, short)]
+    verbose: bool,
+
+    /// Reuse API responses cached on disk for up to this many seconds
+    #[clap(long, value_name = "SECONDS")]
+    cache_ttl: Option<u64>,
+
+    /// Where to cache the API responses, instead of "cache" in the --data-dir
+    #[clap(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Fetch fresh responses instead of reading the cache, e.g. right after an elevator was repaired.
+    /// The fresh responses are still cached for later runs.
+    #[clap(long, alias = "force-refresh", requires = "cache_ttl")]
+    no_cache: bool,
+
+    /// Keep the complete API feature of every equipment, e.g. to pass it on to a webhook
+    #[clap(long)]
+    keep_raw: bool,
+
+    /// Send at most this many API requests per second, no matter the concurrency
+    #[clap(long, value_parser = parse_requests_per_second)]
+    max_requests_per_second: Option<f64>,
+
+    /// Reject API responses larger than this, after decompression
+    #[clap(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_RESPONSE_SIZE)]
+    max_response_size: u64,
+}
+
+fn parse_requests_per_second(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("Expected a positive number, got: {}", value)),
+    }
+}
+
+impl ApiArgs {
+    /// The client for these arguments, caching responses in the `data_dir` unless --cache-dir is given.
+    pub fn client(&self, data_dir: &DataDir) -> Result<ApiClient, Error> {
+        let app_token = match (&self.app_token, &self.token_file) {
+            (Some(app_token), _) => app_token.clone(),
+            (None, Some(token_file)) => read_token_file(token_file)?,
+            (None, None) => app_token_from_env()?,
+        };
+        let mut client = ApiClient::new(
+            app_token,
+            &self.user_agent,
+            Duration::from_secs(self.timeout),
+        )?
+        .with_base_url(&self.api_url)
+        .with_verbose(self.verbose)
+        .with_keep_raw(self.keep_raw)
+        .with_max_response_size(self.max_response_size);
+        if let Some(ttl) = self.cache_ttl {
+            let cache_dir = self
+                .cache_dir
+                .clone()
+                .unwrap_or_else(|| data_dir.cache_dir());
+            client = client
+                .with_cache(ResponseCache::new(cache_dir, Duration::from_secs(ttl)))
+                .with_force_refresh(self.no_cache);
+        }
+        if let Some(requests_per_second) = self.max_requests_per_second {
+            client = client.with_rate_limit(requests_per_second);
+        }
+        Ok(client)
+    }
+}
+
+/// A client for the accessibility-cloud API.
+/// The underlying connection pool is shared by all requests made through it.
+pub struct ApiClient {
+    client: reqwest::blocking::Client,
+    base_url: String,
+    app_token: String,
+    timeout: Duration,
+    cache: Option<ResponseCache>,
+    force_refresh: bool,
+    rate_limiter: Option<RateLimiter>,
+    verbose: bool,
+    keep_raw: bool,
+    max_response_size: u64,
 }
 
-pub fn get_equipments(list: &EquipmentList) -> Result<Vec<Equipment>, Box<dyn Error>> {
-    let access_token = std::env::var("WHEELMAP_TOKEN")?;
-    let request = reqwest::blocking::get(format!(
-        "https://accessibility-cloud.freetls.fastly.net/equipment-infos.json?appToken={}&latitude={}&longitude={}&accuracy=500",
-        &access_token, list.latitude, list.longitude
-    ))?;
+impl ApiClient {
+    pub fn new(app_token: String, user_agent: &str, timeout: Duration) -> reqwest::Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(user_agent)
+            .timeout(timeout)
+            // Ask for compressed responses, they are decompressed before parsing.
+            // Servers that don't support compression simply respond uncompressed.
+            .gzip(true)
+            .deflate(true)
+            .brotli(true)
+            .build()?;
+        Ok(Self {
+            client,
+            base_url: DEFAULT_API_URL.to_owned(),
+            app_token,
+            timeout,
+            cache: None,
+            force_refresh: false,
+            rate_limiter: None,
+            verbose: false,
+            keep_raw: false,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Send requests to another accessibility-cloud instance than [`DEFAULT_API_URL`].
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Print the best matching candidates and their scores for every search.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Keep the complete GeoJSON feature of every equipment, see [`Equipment::raw`].
+    /// Off by default, as the features can be large.
+    pub fn with_keep_raw(mut self, keep_raw: bool) -> Self {
+        self.keep_raw = keep_raw;
+        self
+    }
+
+    /// Reject responses larger than `max_size` bytes instead of reading them into memory,
+    /// [`DEFAULT_MAX_RESPONSE_SIZE`] by default. Compressed responses are limited after decompression.
+    pub fn with_max_response_size(mut self, max_size: u64) -> Self {
+        self.max_response_size = max_size;
+        self
+    }
+
+    /// Serve requests from the given cache while its entries are fresh.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Never serve requests from the cache, but keep storing the fresh responses in it.
+    pub fn with_force_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    /// Send at most `requests_per_second` requests, shared by all threads using this client.
+    pub fn with_rate_limit(mut self, requests_per_second: f64) -> Self {
+        self.rate_limiter = Some(RateLimiter::new(requests_per_second));
+        self
+    }
+
+    /// Create a client using the app token from [`app_token_from_env`],
+    /// the default User-Agent and a 30 second timeout.
+    pub fn from_env() -> Result<Self, Error> {
+        Ok(Self::new(
+            app_token_from_env()?,
+            DEFAULT_USER_AGENT,
+            Duration::from_secs(30),
+        )?)
+    }
+
+    /// Send a GET request for `path` below the API URL, waiting for the rate limit if there is one.
+    /// Only connection failures and timeouts are retried here, not error responses.
+    fn send_request(&self, path: &str, query: &[(&str, String)]) -> Result<Response, Error> {
+        retry_with_backoff(NUM_ATTEMPTS, Duration::from_millis(500), || {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.wait();
+            }
+            self.client
+                .get(format!("{}/{}", self.base_url.trim_end_matches('/'), path))
+                .query(&[("appToken", self.app_token.clone())])
+                .query(query)
+                .send()
+                .map_err(|mut err| {
+                    // Don't leak the app token into the logs
+                    if let Some(url) = err.url_mut() {
+                        url.set_query(None);
+                    }
+                    err
+                })
+        })
+        .map_err(|err| request_error(err, self.timeout))
+    }
+
+    /// The response to a GET request for `path` below the API URL, see [`Self::send_request`].
+    /// Rate limited responses are retried after the delay the API asks for with `Retry-After`,
+    /// or with a longer backoff than other failures if it doesn't say.
+    fn get(&self, path: &str, query: &[(&str, String)]) -> Result<String, Error> {
+        let mut rate_limited = 0;
+        let request = loop {
+            let response = self.send_request(path, query)?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS || rate_limited >= NUM_ATTEMPTS {
+                break response;
+            }
+            let delay = retry_after(&response)
+                .unwrap_or(RATE_LIMIT_BACKOFF * 2u32.pow(rate_limited))
+                .min(MAX_RATE_LIMIT_BACKOFF);
+            println!(
+                "🐢 Rate limited by the API - ⏳ retrying in {} s!",
+                delay.as_secs_f32()
+            );
+            std::thread::sleep(delay);
+            rate_limited += 1;
+        };
+
+        match request.status() {
+            status if status.is_success() => {}
+            status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                return Err(EquipmentAccessError::Unauthorized { status }.into())
+            }
+            StatusCode::TOO_MANY_REQUESTS => return Err(EquipmentAccessError::RateLimited.into()),
+            status => {
+                return Err(EquipmentAccessError::HTTPRequestError {
+                    status,
+                    response_text: read_body(request, self.max_response_size)
+                        .unwrap_or_else(|err| format!("No text received! {}", err)),
+                }
+                .into())
+            }
+        }
+
+        read_body(request, self.max_response_size)
+    }
+
+    /// Fetch a single page of the equipment-infos response, starting at the `skip`th feature.
+    fn fetch_page(&self, query: &[(&str, String)], skip: usize) -> Result<String, Error> {
+        let mut query = query.to_vec();
+        query.push(("skip", skip.to_string()));
+        self.get("equipment-infos.json", &query)
+    }
+
+    /// Fetch a single equipment by the `_id` of its accessibility-cloud feature, see [`Equipment::id`].
+    /// Bypasses the cache, as this is meant for watching a single elevator closely.
+    pub fn get_equipment_by_id(&self, id: &str) -> Result<Equipment, Error> {
+        let path = format!("equipment-infos/{}.json", encode_path_segment(id));
+        let json: Value = serde_json::from_str(&self.get(&path, &[])?)?;
+        Ok(parse_equipment(&json)?)
+    }
 
-    if !request.status().is_success() {
-        return Err(EquipmentAccessError::HTTPRequestError {
-            status: request.status(),
-            response_text: request.text().unwrap_or("No text received!".to_owned()),
+    /// The API returns large result sets in pages, so keep requesting
+    /// until all features announced by the total count were received.
+    /// Returns the first page with the features of all pages merged into it.
+    fn fetch_all_pages(&self, query: &[(&str, String)]) -> Result<String, Error> {
+        let first_page = self.fetch_page(query, 0)?;
+        let mut json: Value = serde_json::from_str(&first_page)?;
+        let total_count = ["totalFeatureCount", "totalCount"]
+            .iter()
+            .find_map(|key| json.get(key).and_then(Value::as_u64));
+        let (Some(total_count), Some(Value::Array(features))) =
+            (total_count, json.get_mut("features"))
+        else {
+            return Ok(first_page);
+        };
+
+        while (features.len() as u64) < total_count {
+            let page = self.fetch_page(query, features.len())?;
+            let page: Value = serde_json::from_str(&page)?;
+            match page.get("features").and_then(Value::as_array) {
+                Some(page_features) if !page_features.is_empty() => {
+                    features.extend(page_features.iter().cloned())
+                }
+                // Don't ask forever if the count was wrong
+                _ => break,
+            }
         }
-        .into());
+
+        Ok(json.to_string())
     }
 
-    let json_string = request.text()?;
-    let json: Value = serde_json::from_str(&json_string)?;
+    /// Fetch the raw equipment-infos response for the given location, or read it from its file.
+    /// Equipment of a place is requested by its id, the `accuracy` only applies to coordinates.
+    /// Uses the cache if one is configured and it holds a fresh response, unless forced to refresh.
+    fn fetch(&self, location: &Location, accuracy: u32) -> Result<String, Error> {
+        let query = match location {
+            Location::Coordinates {
+                latitude,
+                longitude,
+            } => coordinates_query(*latitude, *longitude, accuracy),
+            Location::Place { place_id } => vec![("placeInfoId", place_id.clone())],
+            Location::File { file } => {
+                return std::fs::read_to_string(file).map_err(|source| Error::EquipmentFile {
+                    path: file.clone(),
+                    source,
+                })
+            }
+        };
+        if let Some(response) = self
+            .cache
+            .as_ref()
+            .filter(|_| !self.force_refresh)
+            .and_then(|cache| cache.get(&self.base_url, &query))
+        {
+            return Ok(response);
+        }
+
+        let response = self.fetch_all_pages(&query)?;
+        if let Some(cache) = &self.cache {
+            // A failure to cache shouldn't fail the request itself
+            cache.store(&self.base_url, &query, &response).ok();
+        }
+        Ok(response)
+    }
 
-    if let Some(features) = json.get("features") {
-        let equipments = parse_equipment_list(features);
+    /// Fetch the equipments around the list's coordinates and match each search against them.
+    /// Returns the outcome of every search, in the order of `list.equipment_searches`.
+    pub fn search_equipments(&self, list: &EquipmentList) -> Result<Vec<SearchResult>, Error> {
+        Ok(self
+            .validate_searches(list)?
+            .into_iter()
+            .map(|validation| (validation.search, validation.result))
+            .collect())
+    }
 
-        match equipments {
-            Ok(source_equipments) => {
-                let mut corpus = ngrammatic::CorpusBuilder::new().finish();
-                for equipment in &source_equipments {
-                    corpus.add_text(&equipment.name);
+    /// Like [`ApiClient::search_equipments`], but also reports how well every search matched,
+    /// e.g. to catch typos in a list before relying on it.
+    ///
+    /// If the list has coordinates and an `accuracy_max`, searches that can't be matched are retried
+    /// with the search radius doubled each time, up to `accuracy_max`.
+    /// Lists with coordinates out of range fail without asking the API.
+    pub fn validate_searches(&self, list: &EquipmentList) -> Result<Vec<SearchValidation>, Error> {
+        list.location.validate()?;
+        let accuracy_max = match list.location {
+            Location::Coordinates { .. } => list.accuracy_max.unwrap_or(DEFAULT_ACCURACY),
+            // There is no radius to widen
+            Location::Place { .. } | Location::File { .. } => DEFAULT_ACCURACY,
+        };
+        let mut accuracy = DEFAULT_ACCURACY;
+        loop {
+            let result = self.match_list_at(list, accuracy);
+            let all_matched = match &result {
+                Ok(validations) => validations.iter().all(|v| v.result.is_ok()),
+                Err(Error::Access(EquipmentAccessError::NoEquipmentFound { .. })) => false,
+                // Widening the radius won't help with other errors
+                Err(_) => true,
+            };
+            if all_matched || accuracy >= accuracy_max {
+                if accuracy > DEFAULT_ACCURACY && all_matched {
+                    println!(
+                        "🔭 Found all elevators near {} within {} m",
+                        list.location, accuracy
+                    );
                 }
+                return result;
+            }
+            accuracy = accuracy.saturating_mul(2).min(accuracy_max);
+        }
+    }
 
-                let mut results = Vec::new();
-
-                for search in &list.equipment_searches {
-                    let query_result = corpus.search(&search, 0.4);
-
-                    if let Some(equipment) = query_result.first().and_then(|result_name| {
-                        source_equipments
-                            .iter()
-                            .find(|equipment| equipment.name == result_name.text)
-                    }) {
-                        results.push(equipment.clone());
-                    } else {
-                        return Err(Box::new(EquipmentAccessError::CannotFindEquipment {
-                            query_text: search.to_owned(),
-                        }));
+    /// Fetch the equipments of the list's location and match its searches.
+    /// Around coordinates, only equipments within `accuracy` meters are fetched.
+    fn match_list_at(
+        &self,
+        list: &EquipmentList,
+        accuracy: u32,
+    ) -> Result<Vec<SearchValidation>, Error> {
+        let json_string = self.fetch(&list.location, accuracy)?;
+        let json: Value = serde_json::from_str(&json_string)?;
+
+        if let Some(features) = json.get("features") {
+            let equipments = parse_equipment_list(features, &list.languages, self.keep_raw);
+
+            match equipments {
+                Ok(source_equipments) => {
+                    let mut results = match_searches(
+                        &source_equipments,
+                        &list.equipment_searches,
+                        &list.matching,
+                        self.verbose,
+                    );
+                    for (validation, search) in results.iter_mut().zip(&list.equipment_searches) {
+                        if let Ok(equipment) = &mut validation.result {
+                            if let Some(alias) = &search.alias {
+                                equipment.name = alias.clone();
+                            }
+                            equipment.station = list.station_label.clone();
+                            equipment.priority = list
+                                .priorities
+                                .get(&validation.search)
+                                .copied()
+                                .unwrap_or_default();
+                        }
+                    }
+                    return Ok(results);
+                }
+                Err(errors) if errors.is_empty() => {
+                    return Err(match &list.location {
+                        Location::Coordinates {
+                            latitude,
+                            longitude,
+                        } => EquipmentAccessError::NoEquipmentFound {
+                            latitude: *latitude,
+                            longitude: *longitude,
+                        },
+                        Location::Place { place_id } => EquipmentAccessError::NoEquipmentAtPlace {
+                            place_id: place_id.clone(),
+                        },
+                        Location::File { file } => {
+                            EquipmentAccessError::NoEquipmentInFile { path: file.clone() }
+                        }
                     }
+                    .into())
                 }
+                Err(errors) => return Err(Error::InvalidEquipments(errors)),
+            }
+        }
+        Err(EquipmentAccessError::MissingValue("".to_owned(), json_string).into())
+    }
 
-                return Ok(results);
+    /// Like [`ApiClient::search_equipments`], but separates the matched equipments from the failed searches.
+    pub fn get_equipments(&self, list: &EquipmentList) -> Result<EquipmentResults, Error> {
+        let mut results = EquipmentResults::default();
+        for validation in self.validate_searches(list)? {
+            match validation.result {
+                Ok(equipment) => {
+                    results.equipments.push(equipment);
+                    results.scores.extend(validation.score);
+                }
+                Err(err) => results.errors.push(err),
             }
-            Err(errors) => {
-                let errors_string: String = errors
-                    .iter()
-                    .map(EquipmentAccessError::to_string)
-                    .fold(String::new(), |a, b| a + "\n" + &*b);
-                return Err(format!(
-                    "Errors encountered when sourcing equipments:\n{}",
-                    errors_string
-                )
-                .into());
+        }
+        Ok(results)
+    }
+
+    /// Fetch the lists one after another, only when the next item is requested,
+    /// and yield the equipments of each list as soon as it was matched.
+    /// Failed fetches and unmatched searches are yielded as errors instead of ending the iteration.
+    pub fn iter_equipments<'a>(
+        &'a self,
+        lists: &'a [EquipmentList],
+    ) -> impl Iterator<Item = Result<Equipment, Error>> + 'a {
+        lists
+            .iter()
+            .flat_map(move |list| match self.search_equipments(list) {
+                Ok(results) => results
+                    .into_iter()
+                    .map(|(_, result)| result.map_err(Error::from))
+                    .collect(),
+                Err(err) => vec![Err(err)],
+            })
+    }
+
+    /// Make a single small request to confirm the API is reachable and accepts the app token.
+    /// Bypasses the cache.
+    pub fn check_connection(&self) -> Result<(), Error> {
+        // Any location works, a tiny radius keeps the response small
+        self.fetch_page(&coordinates_query(52.52, 13.40, 1), 0)?;
+        Ok(())
+    }
+
+    /// The equipment-infos response around the coordinates as the API returns it,
+    /// only with the features of all pages merged. Nothing is parsed or filtered and the cache is bypassed,
+    /// which helps finding out how the API's schema changed.
+    pub fn fetch_raw(&self, latitude: f32, longitude: f32, accuracy: u32) -> Result<Value, Error> {
+        let location = Location::Coordinates {
+            latitude,
+            longitude,
+        };
+        location.validate()?;
+        Ok(serde_json::from_str(&self.fetch_all_pages(
+            &coordinates_query(latitude, longitude, accuracy),
+        )?)?)
+    }
+
+    /// List all equipment within `accuracy` meters of the coordinates, including escalators.
+    /// Unlike [`ApiClient::get_equipments`], nothing is filtered or fuzzy matched.
+    /// Features that can't be parsed are skipped and returned as errors.
+    pub fn list_equipment_at(
+        &self,
+        latitude: f32,
+        longitude: f32,
+        accuracy: u32,
+    ) -> Result<EquipmentResults, Error> {
+        let location = Location::Coordinates {
+            latitude,
+            longitude,
+        };
+        location.validate()?;
+        let json: Value = serde_json::from_str(&self.fetch(&location, accuracy)?)?;
+        let features = json
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                EquipmentAccessError::MissingValue("features".to_owned(), json.to_string())
+            })?;
+        let mut results = EquipmentResults::default();
+        for feature in features {
+            match parse_feature(feature, &[], self.keep_raw) {
+                Ok(equipment) => results.equipments.push(equipment),
+                Err(err) => results.errors.push(err),
             }
         }
+        Ok(results)
+    }
+
+    /// Fetch the equipments of all lists, with at most `concurrency` requests in flight.
+    /// The results are returned in the same order as `lists`.
+    pub fn get_all_equipments(
+        &self,
+        lists: &[EquipmentList],
+        concurrency: usize,
+    ) -> Vec<Result<EquipmentResults, Error>> {
+        let next_list = AtomicUsize::new(0);
+        let fetch_remaining = || {
+            let mut results = Vec::new();
+            loop {
+                let index = next_list.fetch_add(1, Ordering::Relaxed);
+                let Some(list) = lists.get(index) else {
+                    return results;
+                };
+                results.push((index, self.get_equipments(list)));
+            }
+        };
+
+        let mut results: Vec<_> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..concurrency.clamp(1, lists.len().max(1)))
+                .map(|_| scope.spawn(fetch_remaining))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+/// Fetch the equipments of a single list using [`ApiClient::from_env`].
+pub fn get_equipments(list: &EquipmentList) -> Result<EquipmentResults, Error> {
+    ApiClient::from_env()?.get_equipments(list)
+}
+
+/// Match the searches of a single list using [`ApiClient::from_env`], see [`ApiClient::validate_searches`].
+pub fn validate_searches(list: &EquipmentList) -> Result<Vec<SearchValidation>, Error> {
+    ApiClient::from_env()?.validate_searches(list)
+}
+
+/// List all equipment around the coordinates using [`ApiClient::from_env`].
+pub fn list_equipment_at(
+    latitude: f32,
+    longitude: f32,
+    accuracy: u32,
+) -> Result<EquipmentResults, Error> {
+    ApiClient::from_env()?.list_equipment_at(latitude, longitude, accuracy)
+}
+
+/// How many candidates are printed per search in verbose mode.
+const NUM_VERBOSE_CANDIDATES: usize = 5;
+
+/// The outcome of a single search, together with how well it matched.
+#[derive(Debug)]
+pub struct SearchValidation {
+    pub search: String,
+    pub result: Result<Equipment, EquipmentAccessError>,
+    /// The similarity of the matched name to the search, between 0 and 1.
+    /// Exact searches always score 1.
+    pub score: Option<f32>,
+}
+
+/// Find the best fuzzy match among `equipments` for every search, see [`MatchOptions`].
+/// Searches starting with "=" must instead match an equipment's name exactly, ignoring case.
+/// If `verbose` is set, the best candidates of each search are printed with their scores.
+fn match_searches(
+    equipments: &[Equipment],
+    searches: &[EquipmentSearch],
+    options: &MatchOptions,
+    verbose: bool,
+) -> Vec<SearchValidation> {
+    let pad = if options.pad { Pad::Auto } else { Pad::None };
+    let mut corpus = CorpusBuilder::new()
+        .arity(options.arity)
+        .pad_full(pad)
+        .finish();
+    for equipment in equipments {
+        corpus.add_text(&equipment.name);
+    }
+
+    searches
+        .iter()
+        .map(|search| {
+            let search = &search.query;
+            let not_found = || EquipmentAccessError::CannotFindEquipment {
+                query_text: search.to_owned(),
+            };
+
+            // A leading "=" asks for an exact (case-insensitive) name instead of a fuzzy match
+            if let Some(exact_name) = search.strip_prefix('=') {
+                let exact_name = exact_name.to_lowercase();
+                let equipment = equipments
+                    .iter()
+                    .find(|equipment| equipment.name.to_lowercase() == exact_name);
+                return SearchValidation {
+                    search: search.to_owned(),
+                    result: equipment.cloned().ok_or_else(not_found),
+                    score: equipment.map(|_| 1.0),
+                };
+            }
+
+            let query_result = corpus.search(search, 0.4);
+
+            if verbose {
+                let candidates: String = query_result
+                    .iter()
+                    .take(NUM_VERBOSE_CANDIDATES)
+                    .map(|candidate| format!("\n  {:.3} {}", candidate.similarity, candidate.text))
+                    .collect();
+                // Print everything at once, other lists may be matched concurrently
+                println!("🔎 Candidates for \"{}\":{}", search, candidates);
+            }
+
+            let best_match = query_result.first().and_then(|result_name| {
+                equipments
+                    .iter()
+                    .find(|equipment| equipment.name == result_name.text)
+                    .map(|equipment| (equipment.clone(), result_name.similarity))
+            });
+            SearchValidation {
+                search: search.to_owned(),
+                score: best_match.as_ref().map(|(_, score)| *score),
+                result: best_match
+                    .map(|(equipment, _)| equipment)
+                    .ok_or_else(not_found),
+            }
+        })
+        .collect()
+}
+
+/// Some data sources encode the working status as a string or number instead of a boolean.
+fn parse_working(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(working) => Some(*working),
+        Value::String(working) => match working.trim().to_lowercase().as_str() {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        },
+        Value::Number(working) => match working.as_u64() {
+            Some(1) => Some(true),
+            Some(0) => Some(false),
+            _ => None,
+        },
+        _ => None,
     }
-    Err(Box::new(EquipmentAccessError::MissingValue(
-        "".to_owned(),
-        json_string,
-    )))
 }
 
-fn parse_equipment(json: &Value) -> Result<Equipment, EquipmentAccessError> {
+/// The languages equipment names are taken from if an [`EquipmentList`] doesn't specify any.
+pub const DEFAULT_LANGUAGES: &[&str] = &["de"];
+
+/// The description in the first of `languages` it is available in.
+/// Descriptions that are a plain string instead of one per language are used as they are.
+fn localized_description<'a>(description: &'a Value, languages: &[String]) -> Option<&'a str> {
+    let in_language = |language: &str| description.get(language)?.as_str();
+    let localized = if languages.is_empty() {
+        DEFAULT_LANGUAGES
+            .iter()
+            .find_map(|language| in_language(language))
+    } else {
+        languages.iter().find_map(|language| in_language(language))
+    };
+    localized.or_else(|| description.as_str())
+}
+
+/// The keys the properties of a feature are looked up under, in order of preference.
+/// The API has renamed properties before, so the known alternative names are tried as well.
+const WORKING_KEYS: &[&str] = &["isWorking", "operating", "isOperating"];
+const DESCRIPTION_KEYS: &[&str] = &["description", "name"];
+const PLACE_KEYS: &[&str] = &["placeInfoName", "placeName"];
+const CATEGORY_KEYS: &[&str] = &["category", "equipmentCategory"];
+const MAINTENANCE_KEYS: &[&str] = &["isUnderMaintenance", "underMaintenance"];
+
+/// The alternative keys that were already warned about, so every feature doesn't repeat the warning.
+static WARNED_FALLBACK_KEYS: Mutex<BTreeSet<&str>> = Mutex::new(BTreeSet::new());
+
+/// The first of `keys` that is set in `properties` and can be parsed by `parse`.
+/// Warns once if it is found under an alternative key, as the API's schema has probably changed.
+fn property<'a, T>(
+    properties: &'a Value,
+    keys: &[&'static str],
+    parse: impl Fn(&'a Value) -> Option<T>,
+) -> Option<T> {
+    keys.iter().enumerate().find_map(|(i, key)| {
+        let value = parse(properties.get(key)?)?;
+        if i > 0 && WARNED_FALLBACK_KEYS.lock().unwrap().insert(key) {
+            println!(
+                "⚠️ Found \"{}\" under \"{}\" instead, the API's schema may have changed",
+                keys[0], key
+            );
+        }
+        Some(value)
+    })
+}
+
+/// Parse a single GeoJSON feature of the equipment-infos endpoint,
+/// taking the name from the description in one of the [`DEFAULT_LANGUAGES`].
+/// Missing optional properties fall back to defaults, only missing `properties` is an error.
+/// Properties the API has renamed before are also found under their alternative names.
+pub fn parse_equipment(json: &Value) -> Result<Equipment, EquipmentAccessError> {
+    parse_equipment_in(json, &[])
+}
+
+/// Like [`parse_equipment`], but takes the name from the description in the first of `languages`
+/// it is available in. If `languages` is empty, the [`DEFAULT_LANGUAGES`] are used.
+pub fn parse_equipment_in(
+    json: &Value,
+    languages: &[String],
+) -> Result<Equipment, EquipmentAccessError> {
     if let Some(properties) = &json.get("properties") {
-        let working = properties
-            .get("isWorking")
-            .unwrap_or(&Value::default())
-            .as_bool();
-        let name = properties
-            .get("description")
-            .and_then(|description| description.get("de").unwrap_or(description).as_str())
-            .unwrap_or("Cannot find description!")
-            .to_owned();
-        let category = properties
-            .get("category")
-            .and_then(Value::as_str)
+        let working = property(properties, WORKING_KEYS, parse_working);
+        let name = property(properties, DESCRIPTION_KEYS, |description| {
+            localized_description(description, languages)
+        })
+        .unwrap_or("Cannot find description!")
+        .to_owned();
+        let category = property(properties, CATEGORY_KEYS, Value::as_str)
             .unwrap_or("elevator")
             .to_owned();
-        let place = properties
-            .get("placeInfoName")
-            .and_then(Value::as_str)
-            .map(str::to_owned);
+        let place = property(properties, PLACE_KEYS, Value::as_str).map(str::to_owned);
+        // Not every data source reports planned maintenance, so it is only assumed if it says so
+        let maintenance = property(properties, MAINTENANCE_KEYS, parse_working).unwrap_or(false);
+        let id = json.get("_id").and_then(Value::as_str).map(str::to_owned);
+        let coordinates = json
+            .get("geometry")
+            .and_then(|geometry| geometry.get("coordinates"))
+            .and_then(|coordinates| serde_json::from_value(coordinates.clone()).ok());
+        let last_update = ["stateLastUpdate", "lastUpdate"]
+            .iter()
+            .filter_map(|key| properties.get(key)?.as_str())
+            .find_map(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Utc));
 
         Ok(Equipment {
+            id,
             name,
             category,
             working,
             place,
+            station: None,
+            last_update,
+            coordinates,
+            priority: Priority::Normal,
+            maintenance,
+            recently_broken: false,
+            raw: None,
         })
     } else {
         Err(EquipmentAccessError::MissingValue(
@@ -174,11 +1882,28 @@ fn parse_equipment(json: &Value) -> Result<Equipment, EquipmentAccessError> {
     }
 }
 
-fn parse_equipment_list(json: &Value) -> Result<Vec<Equipment>, Vec<EquipmentAccessError>> {
+/// Like [`parse_equipment_in`], but also keeps the complete feature if `keep_raw` is set.
+fn parse_feature(
+    json: &Value,
+    languages: &[String],
+    keep_raw: bool,
+) -> Result<Equipment, EquipmentAccessError> {
+    let mut equipment = parse_equipment_in(json, languages)?;
+    if keep_raw {
+        equipment.raw = Some(json.clone());
+    }
+    Ok(equipment)
+}
+
+fn parse_equipment_list(
+    json: &Value,
+    languages: &[String],
+    keep_raw: bool,
+) -> Result<Vec<Equipment>, Vec<EquipmentAccessError>> {
     if let Some(equipments) = json.as_array() {
         let (equipments, errors): (Vec<_>, _) = equipments
             .iter()
-            .map(parse_equipment)
+            .map(|feature| parse_feature(feature, languages, keep_raw))
             .partition(Result::is_ok);
 
         let equipments: Vec<Equipment> = equipments
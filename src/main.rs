@@ -1,7 +1,12 @@
-use clap::{Parser, Subcommand};
-use elstatus::*;
-use std::{error::Error, path::PathBuf};
-use tera::Tera;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use elstatus::{notifier::Notifier, run::RunContext, summary::StatusSummary, *};
+use std::{
+    error::Error,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -12,6 +17,66 @@ struct Cli {
     /// The file path to a JSON file containing the list of elevators.
     /// Uses equipments.json in the current working dir by default.
     elevator_list: Option<PathBuf>,
+    #[arg(long, value_name = "FILE_PATH")]
+    /// A JSON file with the options of the backends, one section per backend, e.g.
+    /// { "email": { "smtp_server": "smtp.example.org", "smtp_password": "${SMTP_PASSWORD}" }, "display": { ... } }.
+    /// The sections are named email, display, matrix, webhook and report, and take the same options
+    /// as the command line, with underscores instead of dashes. Options given on the command line win.
+    /// Sections are only used by their own subcommand, and all of them by all and config-check.
+    config: Option<PathBuf>,
+    #[arg(long, value_name = "FILE_PATH", conflicts_with = "elevator_list")]
+    /// Read the equipments from a JSON file instead of fetching them from the API,
+    /// e.g. examples/elstatus.broken.json. Useful for demos and testing without network access.
+    fixture: Option<PathBuf>,
+    #[arg(long, default_value_t = 4)]
+    /// The maximum number of elevator lists to fetch at the same time.
+    concurrency: usize,
+    #[arg(long, value_parser = humantime::parse_duration)]
+    /// Keep running and repeat the update in this interval, e.g. "15m" or "1h".
+    /// Stops gracefully on SIGINT/SIGTERM.
+    interval: Option<Duration>,
+    #[arg(long, value_name = "DIR")]
+    /// Keep all generated files in this directory, created if needed:
+    /// the state of previous runs, cached API responses and rendered images.
+    /// By default, state and cache files go to /tmp and render-only images to the current working dir.
+    data_dir: Option<PathBuf>,
+    #[arg(long)]
+    /// Count elevators with an unknown status as working or broken
+    /// for the exit code and the headline of notifications.
+    unknown_as: Option<summary::UnknownAs>,
+    #[arg(long, value_name = "N")]
+    /// Only list the N most important broken elevators in notifications and on the displays,
+    /// followed by how many more are broken. The counts in the headline still include all of them,
+    /// and the webhook always gets the complete list.
+    max_listed: Option<usize>,
+    #[arg(long)]
+    /// Stop at the first elevator list that can't be read or fetched, instead of passing the status
+    /// of the others on along with the error. Nothing is sent or updated then, and the error is returned
+    /// as the failure of the process, as is any other error of a run, even with --interval.
+    fail_fast: bool,
+    #[arg(long, conflicts_with_all = ["fixture", "interval", "elevator_list"])]
+    /// Send a test notification with fabricated elevators through the backends of the subcommand and exit,
+    /// to confirm that the real runs will be delivered. It is sent like any other status, also during quiet hours,
+    /// and nothing is remembered of it, so the next run sends and uploads the real status again.
+    /// The report is written to a temporary file, so the real one is kept.
+    send_test: bool,
+    #[arg(long)]
+    /// Keep track of how well the searches matched across runs and print a summary after each run:
+    /// a histogram of the similarity scores and the number of unmatched searches per station.
+    /// They are also written in the Prometheus text format to elstatus.metrics.prom in the data dir.
+    match_metrics: bool,
+    #[command(flatten)]
+    api: ApiArgs,
+    #[command(flatten)]
+    debounce: debounce::DebounceArgs,
+    #[command(flatten)]
+    quiet_hours: quiet::QuietHoursArgs,
+}
+
+impl Cli {
+    fn data_dir(&self) -> data_dir::DataDir {
+        data_dir::DataDir::new(self.data_dir.clone())
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -20,68 +85,688 @@ enum Command {
     EMail(email::EMailArgs),
     /// Update the epaper displays using OpenEPaperLink
     Display(display::DisplayArgs),
+    /// Render the epaper images to disk without uploading them,
+    /// with the same layout and encoding options as display
+    RenderOnly(display::RenderArgs),
+    /// Send the status to a Matrix room
+    Matrix(matrix::MatrixArgs),
+    /// POST the status as JSON to a URL
+    Webhook(webhook::WebhookArgs),
+    /// Write the status as a self-contained HTML report, e.g. for an internal wiki
+    Html(report::ReportArgs),
+    /// List all equipment around a location, to help writing the list of elevators
+    Discover(DiscoverArgs),
+    /// Print the raw API response for a location, without any parsing or filtering
+    Raw(DiscoverArgs),
+    /// Check the connection to the API, the SMTP server and the access point,
+    /// without sending any email or updating any display
+    Check(CheckArgs),
+    /// Fetch the status once and pass it on to every backend that is configured
+    All(Box<AllArgs>),
+    /// Check the elevator list, the templates and the arguments of every backend that is given for mistakes,
+    /// without any network access
+    ConfigCheck(Box<AllArgs>),
+    /// Match every search of the elevator list against the live data and report how well it matched,
+    /// without sending any notification
+    Validate,
+}
+
+/// The arguments of every backend, a backend is used if any of its arguments are given.
+/// Arguments a backend requires are only checked once it is used.
+#[derive(Args, Debug)]
+#[command(mut_args = |arg| arg.required(false))]
+struct AllArgs {
+    #[command(flatten)]
+    email: Option<email::EMailArgs>,
+    #[command(flatten)]
+    display: Option<display::DisplayArgs>,
+    #[command(flatten)]
+    matrix: Option<matrix::MatrixArgs>,
+    #[command(flatten)]
+    webhook: Option<webhook::WebhookArgs>,
+    #[command(flatten)]
+    report: Option<report::ReportArgs>,
+}
+
+#[derive(Args, Debug)]
+struct CheckArgs {
+    /// smtp server address
+    #[arg(long, requires = "smtp_user")]
+    smtp_server: Option<String>,
+    /// smtp username
+    #[arg(long)]
+    smtp_user: Option<String>,
+    /// smtp password
+    #[arg(long)]
+    smtp_password: Option<String>,
+    /// OAuth2 bearer token to log in with XOAUTH2 instead of the password
+    #[arg(long, env = "SMTP_OAUTH_TOKEN", hide_env_values = true)]
+    smtp_oauth_token: Option<String>,
+    /// URL or IP address of the access point.
+    #[arg(long)]
+    ap_address: Option<String>,
+    /// Accept invalid TLS certificates from the access point
+    #[arg(long)]
+    ap_accept_invalid_certs: bool,
+}
+
+/// Run every check that was configured and report whether all of them succeeded.
+fn check(cli: &Cli, args: &CheckArgs) -> bool {
+    fn report(name: &str, result: Result<(), Box<dyn Error>>) -> bool {
+        match result {
+            Ok(()) => println!("✅ {name}"),
+            Err(err) => {
+                println!("❌ {name}: {err}");
+                return false;
+            }
+        }
+        true
+    }
+
+    let mut success = report(
+        "accessibility-cloud API",
+        cli.api
+            .client(&cli.data_dir())
+            .and_then(|client| client.check_connection())
+            .map_err(Into::into),
+    );
+
+    match (&args.smtp_server, &args.smtp_user) {
+        (Some(server), Some(user)) => {
+            success &= report(
+                "SMTP server",
+                email::check_connection(
+                    server,
+                    user,
+                    args.smtp_password.as_deref(),
+                    args.smtp_oauth_token.as_deref(),
+                ),
+            );
+        }
+        _ => println!("⏭️ SMTP server: skipped, no --smtp-server given"),
+    }
+
+    match &args.ap_address {
+        Some(ap_address) => {
+            success &= report(
+                "Access point",
+                display::check_access_point(
+                    ap_address,
+                    Duration::from_secs(10),
+                    args.ap_accept_invalid_certs,
+                ),
+            );
+        }
+        None => println!("⏭️ Access point: skipped, no --ap-address given"),
+    }
+
+    success
 }
 
-fn read_equipment_list(cli: &Cli) -> (Vec<Equipment>, Vec<Box<dyn Error>>) {
+/// Report every problem with the elevator list and the backend arguments, without any network access,
+/// and whether there were none.
+fn config_check(cli: &Cli, args: &AllArgs) -> bool {
+    fn report(name: &str, problems: Vec<String>) -> bool {
+        if problems.is_empty() {
+            println!("✅ {name}");
+        }
+        for problem in problems.iter() {
+            println!("❌ {name}: {problem}");
+        }
+        problems.is_empty()
+    }
+
+    let mut success = true;
+    match &cli.fixture {
+        Some(fixture) => {
+            let problems = read_fixture(fixture).err().map(|err| err.to_string());
+            success &= report("Fixture", problems.into_iter().collect());
+        }
+        None => match read_lists(cli) {
+            Ok(lists) => {
+                for list in lists.iter() {
+                    success &= report(&list.name(), list.config_problems());
+                }
+            }
+            Err(err) => success &= report("Elevator list", vec![err.to_string()]),
+        },
+    }
+
+    match &args.email {
+        Some(email_args) => success &= report("E-Mail", email_args.config_problems()),
+        None => println!("⏭️ E-Mail: skipped, no email arguments given"),
+    }
+    match &args.display {
+        Some(display_args) => success &= report("Display", display_args.config_problems()),
+        None => println!("⏭️ Display: skipped, no display arguments given"),
+    }
+    match &args.report {
+        Some(report_args) => success &= report("Report", report_args.config_problems()),
+        None => println!("⏭️ Report: skipped, no report arguments given"),
+    }
+
+    success
+}
+
+/// Print how well every search matched and report whether all of them did.
+fn validate(cli: &Cli) -> Result<bool, Box<dyn Error>> {
+    let lists = read_lists(cli)?;
+    let client = cli.api.client(&cli.data_dir())?;
+
+    let mut success = true;
+    for list in lists.iter() {
+        println!("📍 {}", list.name());
+        match client.validate_searches(list) {
+            Ok(validations) => {
+                for validation in validations {
+                    match validation.result {
+                        Ok(equipment) => println!(
+                            "  ✅ \"{}\" matched \"{}\" (score {:.3})",
+                            validation.search,
+                            equipment.name(),
+                            validation.score.unwrap_or_default()
+                        ),
+                        Err(err) => {
+                            println!("  ❌ \"{}\": {}", validation.search, err);
+                            success = false;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                println!("  ❌ {}", err);
+                success = false;
+            }
+        }
+    }
+    Ok(success)
+}
+
+#[derive(Args, Debug)]
+struct DiscoverArgs {
+    #[arg(long, allow_negative_numbers = true)]
+    latitude: f32,
+    #[arg(long, allow_negative_numbers = true)]
+    longitude: f32,
+    /// Search radius in meters
+    #[arg(long, default_value_t = DEFAULT_ACCURACY)]
+    accuracy: u32,
+}
+
+fn discover(cli: &Cli, args: &DiscoverArgs) -> Result<(), elstatus::Error> {
+    let results = cli.api.client(&cli.data_dir())?.list_equipment_at(
+        args.latitude,
+        args.longitude,
+        args.accuracy,
+    )?;
+
+    for equipment in results.equipments.iter() {
+        println!("{}: {}", equipment.category(), equipment.name());
+    }
+    for err in results.errors.iter() {
+        println!("⚠️ Skipped an equipment: {}", err);
+    }
+    Ok(())
+}
+
+fn raw(cli: &Cli, args: &DiscoverArgs) -> Result<(), Box<dyn Error>> {
+    let json =
+        cli.api
+            .client(&cli.data_dir())?
+            .fetch_raw(args.latitude, args.longitude, args.accuracy)?;
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(())
+}
+
+/// Read the elevator lists from the file given with `-e`, or equipments.json.
+/// `${NAME}` in any string is replaced with the environment variable `NAME`.
+fn read_lists(cli: &Cli) -> Result<Vec<EquipmentList>, Box<dyn Error>> {
     let json = std::fs::read_to_string(
         cli.elevator_list
             .clone()
             .unwrap_or_else(|| "./equipments.json".into()),
-    );
-    let json = match json {
-        Ok(json) => json,
-        Err(err) => {
-            return (vec![], vec![Box::new(err)]);
+    )?;
+    let mut json: serde_json::Value = serde_json::from_str(&json)?;
+    config::expand_env_in(&mut json)?;
+    Ok(serde_json::from_value(json)?)
+}
+
+fn read_fixture(path: &Path) -> Result<Vec<Equipment>, Box<dyn Error>> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("Could not read fixture {}: {}", path.display(), err))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// The equipments and errors of all lists, and the searches that couldn't be matched.
+type EquipmentListResult = (Vec<Equipment>, Vec<Box<dyn Error>>, Vec<UnmatchedSearch>);
+
+/// The equipments and errors of all lists, see [`EquipmentListResult`].
+/// With --fail-fast, the first error that kept a list from being read or fetched is returned instead,
+/// searches that couldn't be matched are still only reported.
+fn read_equipment_list(cli: &Cli) -> Result<EquipmentListResult, Box<dyn Error>> {
+    let failed = |err: Box<dyn Error>| {
+        if cli.fail_fast {
+            Err(err)
+        } else {
+            Ok((vec![], vec![err], vec![]))
         }
     };
-    let equipment_list: Result<Vec<EquipmentList>, _> = serde_json::from_str(&json);
-    let equipment_list = match equipment_list {
+    if cli.send_test {
+        return Ok((
+            test_notification::equipments(),
+            test_notification::errors(),
+            vec![],
+        ));
+    }
+    if let Some(fixture) = &cli.fixture {
+        return match read_fixture(fixture) {
+            Ok(equipments) => Ok((equipments, vec![], vec![])),
+            Err(err) => failed(err),
+        };
+    }
+    let equipment_list = match read_lists(cli) {
         Ok(equipment_list) => equipment_list,
-        Err(err) => {
-            return (vec![], vec![Box::new(err)]);
-        }
+        Err(err) => return failed(err),
     };
-    let (equipments, errors): (Vec<_>, Vec<_>) = equipment_list
-        .iter()
-        .map(get_equipments)
-        .partition(Result::is_ok);
-
-    let equipments: Vec<_> = equipments.into_iter().flat_map(Result::unwrap).collect();
-    let errors: Vec<_> = errors
-        .into_iter()
-        .map(Result::err)
-        .map(Option::unwrap)
-        .collect();
+    let client = match cli.api.client(&cli.data_dir()) {
+        Ok(client) => client,
+        Err(err) => return failed(Box::new(err)),
+    };
+    let mut equipments = Vec::new();
+    let mut errors: Vec<Box<dyn Error>> = Vec::new();
+    let results = client.get_all_equipments(&equipment_list, cli.concurrency);
+    let unmatched = unmatched_searches(&equipment_list, &results);
+    if cli.match_metrics {
+        let data_dir = cli.data_dir();
+        let mut metrics = metrics::MatchMetrics::load(&data_dir);
+        metrics.record(&equipment_list, &results);
+        println!("{}", metrics.summary());
+        if let Err(err) = metrics.store(&data_dir) {
+            println!("Could not store the match metrics: {}", err);
+        }
+    }
+    for (list, result) in equipment_list.iter().zip(results) {
+        match result {
+            Ok(results) => {
+                equipments.extend(results.equipments);
+                errors.extend(
+                    results
+                        .errors
+                        .into_iter()
+                        .map(|err| Box::new(err) as Box<dyn Error>),
+                );
+            }
+            Err(err) if cli.fail_fast => return Err(Box::new(err.for_station(list))),
+            Err(err) => errors.push(Box::new(err.for_station(list))),
+        }
+    }
 
-    (equipments, errors)
+    // Lists with overlapping radii may find the same elevator
+    Ok((dedup_equipments(equipments), errors, unmatched))
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse();
-    let (equipments, errors) = read_equipment_list(&cli);
+/// The renderer for the displays, configured by the display arguments if there are any.
+/// The image attached to the status email is rendered by it as well, so it matches the displays.
+/// It is set up once and reused by every run.
+fn display_renderer(cli: &Cli) -> display::DisplayRenderer {
+    let renderer = match &cli.command {
+        Command::Display(display_args) => display_args.renderer(),
+        Command::All(all_args) => all_args.display.as_ref().map_or_else(
+            display::DisplayRenderer::new,
+            display::DisplayArgs::renderer,
+        ),
+        Command::RenderOnly(render_args) => render_args.renderer(),
+        _ => display::DisplayRenderer::new(),
+    };
+    renderer
+        .with_max_listed(cli.max_listed)
+        .with_unknown_as(cli.unknown_as)
+}
+
+/// Fetch the status once and pass it on to the selected command.
+/// `previous_state` is what the displays currently show, if known. It is updated by both `display` and `all`,
+/// so in daemon mode, changes are detected in memory without reading the state file again.
+fn run(
+    cli: &Cli,
+    renderer: &display::DisplayRenderer,
+    run_context: &RunContext,
+    previous_state: &mut Option<Vec<Equipment>>,
+) -> Result<StatusSummary, Box<dyn Error>> {
+    println!("🏁 Run {}", run_context);
+    let data_dir = cli.data_dir();
+    let (mut equipments, errors, unmatched) = read_equipment_list(cli).map_err(FetchFailed)?;
+    if cli.debounce.is_enabled() && !cli.send_test {
+        let now = chrono::Utc::now();
+        let mut history = debounce::StatusHistory::load(&data_dir);
+        equipments = history.debounce(&equipments, now, &cli.debounce);
+        equipments = history.mark_recently_broken(equipments, now, &cli.debounce);
+        if let Err(err) = history.store(&data_dir) {
+            println!("Could not store the status history: {}", err);
+        }
+    }
+    let summary = StatusSummary::new(&equipments, &errors).with_unknown_as(cli.unknown_as);
+
+    let mut notify = true;
+    if cli.quiet_hours.quiet_start.is_some() && !cli.send_test {
+        let last_status = quiet::load_last_status(&data_dir);
+        if let Err(err) = quiet::store_last_status(&data_dir, &equipments) {
+            println!("Could not store the status: {}", err);
+        }
+        notify = cli.quiet_hours.should_notify(
+            chrono::Local::now().time(),
+            last_status.as_deref(),
+            &equipments,
+        );
+    }
 
     for equipment in equipments.iter() {
-        println!("{:?}", equipment);
+        println!("{}", equipment);
     }
 
     for error in errors.iter() {
         println!("Error: {}", error);
     }
 
-    match cli.command {
-        Command::EMail(email_args) => {
-            let mut tera = Tera::default();
-            tera.add_raw_templates(vec![
-                ("status.html", include_str!("templates/status.html")),
-                ("status.txt", include_str!("templates/status.txt")),
-                ("errors.txt", include_str!("templates/errors.txt")),
-            ])?;
+    match &cli.command {
+        Command::EMail(_) | Command::Matrix(_) | Command::Webhook(_) if !notify => {
+            println!("🌙 Quiet hours, not sending any notification");
+        }
+        Command::EMail(email_args) => email::EMailNotifier {
+            args: email_args,
+            max_listed: cli.max_listed,
+            displayed: None,
+            renderer,
+            run: run_context,
+            data_dir: &data_dir,
+        }
+        .notify(&equipments, &errors, &summary)?,
+        Command::Display(display_args) => {
+            display::update_since(
+                &equipments,
+                previous_state.as_deref(),
+                display_args,
+                renderer,
+                run_context,
+                &data_dir,
+            )?;
+            *previous_state = Some(equipments);
+        }
+        Command::RenderOnly(render_args) => display::render(
+            &equipments,
+            renderer,
+            &render_args.image_encoding,
+            run_context,
+            &data_dir,
+        )?,
+        Command::Matrix(matrix_args) => matrix::MatrixNotifier {
+            args: matrix_args,
+            max_listed: cli.max_listed,
+        }
+        .notify(&equipments, &errors, &summary)?,
+        Command::Webhook(webhook_args) => {
+            webhook::send_result(&equipments, &errors, &summary, webhook_args)?
+        }
+        Command::Html(report_args) => {
+            report::write_report(&equipments, &errors, &summary, run_context, report_args)?
+        }
+        Command::All(all_args) => {
+            // The email attaches what the displays were updated with, so it is updated first
+            let updated = display::UpdatedImages::default();
+            let mut notifiers: Vec<Box<dyn Notifier + '_>> = Vec::new();
+            if let Some(display_args) = &all_args.display {
+                notifiers.push(Box::new(display::DisplayNotifier {
+                    args: display_args,
+                    previous: previous_state.as_deref(),
+                    updated: &updated,
+                    renderer,
+                    run: run_context,
+                    data_dir: &data_dir,
+                }));
+            }
+            // The report isn't a notification, so it is kept up to date during quiet hours
+            if let Some(report_args) = &all_args.report {
+                notifiers.push(Box::new(report::ReportNotifier {
+                    args: report_args,
+                    run: run_context,
+                }));
+            }
+            if !notify {
+                println!("🌙 Quiet hours, not sending any notification");
+            } else {
+                if let Some(email_args) = &all_args.email {
+                    notifiers.push(Box::new(email::EMailNotifier {
+                        args: email_args,
+                        max_listed: cli.max_listed,
+                        displayed: Some(&updated),
+                        renderer,
+                        run: run_context,
+                        data_dir: &data_dir,
+                    }));
+                }
+                if let Some(matrix_args) = &all_args.matrix {
+                    notifiers.push(Box::new(matrix::MatrixNotifier {
+                        args: matrix_args,
+                        max_listed: cli.max_listed,
+                    }));
+                }
+                if let Some(webhook_args) = &all_args.webhook {
+                    notifiers.push(Box::new(webhook_args));
+                }
+            }
+
+            // Every backend gets its chance, even if another one failed
+            let mut failures = Vec::new();
+            for notifier in notifiers.iter() {
+                if let Err(err) = notifier.notify(&equipments, &errors, &summary) {
+                    println!("❌ {}: {}", notifier.name(), err);
+                    failures.push(notifier.name().to_owned());
+                }
+            }
+            drop(notifiers);
+            // Like with the display subcommand, the next run only updates the displays if this changes
+            if all_args.display.is_some() && !failures.iter().any(|name| name == "Display") {
+                *previous_state = Some(equipments);
+            }
+            if !failures.is_empty() {
+                return Err(format!("Failed backends: {}", failures.join(", ")).into());
+            }
+        }
+        Command::Discover(_)
+        | Command::Raw(_)
+        | Command::Check(_)
+        | Command::ConfigCheck(_)
+        | Command::Validate => {
+            unreachable!("Command doesn't need the list of elevators")
+        }
+    }
+
+    // All in one place, so stale searches can be fixed in one go
+    if !unmatched.is_empty() {
+        let unmatched: Vec<_> = unmatched.iter().map(ToString::to_string).collect();
+        println!("🔎 Never matched: {}", unmatched.join(", "));
+    }
+    Ok(summary)
+}
+
+/// The sections of the config file the subcommand takes its options from, see `--config`.
+fn config_sections(subcommand: &str) -> &'static [&'static str] {
+    match subcommand {
+        "all" | "config-check" => &["email", "display", "matrix", "webhook", "report"],
+        "e-mail" => &["email"],
+        "display" => &["display"],
+        "matrix" => &["matrix"],
+        "webhook" => &["webhook"],
+        "html" => &["report"],
+        _ => &[],
+    }
+}
+
+/// The options of the backend sections in the `--config` file that apply to the subcommand,
+/// except those that are given on the command line, see [`config::section_args`].
+fn config_args(
+    config: &Path,
+    subcommand: &str,
+    args: &[OsString],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let json = std::fs::read_to_string(config)
+        .map_err(|err| format!("Could not read config file {}: {}", config.display(), err))?;
+    let mut json: serde_json::Value = serde_json::from_str(&json)?;
+    config::expand_env_in(&mut json)?;
+
+    let given: Vec<String> = args
+        .iter()
+        .filter_map(|arg| arg.to_str()?.strip_prefix("--"))
+        .map(|option| option.split('=').next().unwrap_or_default().to_owned())
+        .collect();
+    let mut config_args = Vec::new();
+    for section in config_sections(subcommand) {
+        if let Some(options) = json.get(section) {
+            config_args.extend(config::section_args(section, options, &given)?);
+        }
+    }
+    Ok(config_args)
+}
+
+/// Parse the command line, with the options from the `--config` file added to it.
+/// As the subcommand may require options that are only in the file,
+/// the command line is first parsed leniently to find the file and the subcommand.
+fn parse_cli() -> Result<Cli, Box<dyn Error>> {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    if let Ok(matches) = Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+    {
+        if let (Some(config), Some(subcommand)) = (
+            matches.get_one::<PathBuf>("config"),
+            matches.subcommand_name(),
+        ) {
+            let config_args = config_args(config, subcommand, &args)?;
+            args.extend(config_args.into_iter().map(OsString::from));
+        }
+    }
+    Ok(Cli::parse_from(args))
+}
+
+/// Exit code when the command itself failed, e.g. because no email could be sent.
+const EXIT_COMMAND_FAILED: i32 = 4;
+
+/// Exit code when an elevator list couldn't be read or fetched with --fail-fast,
+/// the same as when some stations couldn't be checked without it.
+const EXIT_FETCH_FAILED: i32 = 2;
 
-            email::send_result(&equipments, &errors, &tera, &email_args);
+/// An elevator list that couldn't be read or fetched with --fail-fast, see [`EXIT_FETCH_FAILED`].
+#[derive(Debug)]
+struct FetchFailed(Box<dyn Error>);
+
+impl std::fmt::Display for FetchFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for FetchFailed {}
+
+/// Stop the process because of an error with --fail-fast, with the error on stderr.
+/// Unlike returning it from `main`, the exit code doesn't look like a broken elevator.
+fn fail_fast(err: Box<dyn Error>) -> ! {
+    eprintln!("Error: {}", err);
+    std::process::exit(if err.is::<FetchFailed>() {
+        EXIT_FETCH_FAILED
+    } else {
+        EXIT_COMMAND_FAILED
+    })
+}
 
-            email::send_errors(&errors, &tera, &email_args);
+/// Send the test notification, see `--send-test`, and exit.
+/// The run keeps its state in a temporary data dir, so it doesn't count as the last status,
+/// and writes the report there, so the real one isn't replaced.
+fn send_test(mut cli: Cli) -> Result<(), Box<dyn Error>> {
+    if let Command::RenderOnly(_) = &cli.command {
+        return Err("render-only doesn't send anything, use display to test the upload".into());
+    }
+    let data_dir = cli.data_dir();
+    let test_dir = tempfile::tempdir()?;
+    let test_data_dir = data_dir::DataDir::new(Some(test_dir.path().to_owned()));
+    cli.data_dir = Some(test_dir.path().to_owned());
+    let report_args = match &mut cli.command {
+        Command::Html(report_args) => Some(report_args),
+        Command::All(all_args) => all_args.report.as_mut(),
+        _ => None,
+    };
+    if let Some(report_args) = report_args {
+        report_args.set_report_path(test_dir.path().join("report.html"));
+    }
+    let renderer = display_renderer(&cli);
+    let result = run(&cli, &renderer, &RunContext::new(), &mut None);
+    // The tags show the test now, so the next run has to upload the real status again
+    if display::uploaded_any(&test_data_dir) {
+        display::forget_state(&data_dir)?;
+    }
+    match result {
+        Ok(_) => {
+            println!("✅ Test notification sent");
             Ok(())
         }
-        Command::Display(display_args) => display::update(&equipments, &display_args),
+        Err(err) => {
+            println!("Error: {}", err);
+            std::process::exit(EXIT_COMMAND_FAILED);
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = parse_cli()?;
+    cli.data_dir().create()?;
+    if let Command::Discover(args) = &cli.command {
+        return Ok(discover(&cli, args)?);
+    }
+    if let Command::Raw(args) = &cli.command {
+        return raw(&cli, args);
+    }
+    if let Command::Check(args) = &cli.command {
+        std::process::exit(if check(&cli, args) { 0 } else { 1 });
+    }
+    if let Command::ConfigCheck(args) = &cli.command {
+        std::process::exit(if config_check(&cli, args) { 0 } else { 1 });
+    }
+    if let Command::Validate = &cli.command {
+        std::process::exit(if validate(&cli)? { 0 } else { 1 });
+    }
+    if cli.send_test {
+        return send_test(cli);
+    }
+    let mut previous_state = display::load_state(&cli.data_dir());
+    let renderer = display_renderer(&cli);
+
+    let Some(interval) = cli.interval else {
+        // See StatusSummary::exit_code for the meaning of the exit codes
+        match run(&cli, &renderer, &RunContext::new(), &mut previous_state) {
+            Ok(summary) => std::process::exit(summary.exit_code()),
+            Err(err) if cli.fail_fast => fail_fast(err),
+            Err(err) => {
+                println!("Error: {}", err);
+                std::process::exit(EXIT_COMMAND_FAILED);
+            }
+        }
+    };
+
+    let (shutdown_sender, shutdown) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        shutdown_sender.send(()).ok();
+    })?;
+
+    loop {
+        // A failed iteration shouldn't stop the daemon, the next one may succeed
+        match run(&cli, &renderer, &RunContext::new(), &mut previous_state) {
+            Err(err) if cli.fail_fast => fail_fast(err),
+            Err(err) => println!("Error: {}", err),
+            Ok(_) => {}
+        }
+
+        println!("⏳ Next update in {}", humantime::format_duration(interval));
+        if shutdown.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+            println!("👋 Shutting down");
+            return Ok(());
+        }
     }
 }
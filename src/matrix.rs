@@ -0,0 +1,142 @@
+use crate::{
+    notifier::Notifier,
+    summary::{limit_broken, StatusSummary},
+    Equipment,
+};
+use clap::Args;
+use reqwest::{blocking::Client, Url};
+use serde_json::json;
+use std::{
+    error::Error,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Args, Debug)]
+pub struct MatrixArgs {
+    /// URL of the Matrix homeserver, e.g. https://matrix.org
+    #[clap(long)]
+    homeserver_url: String,
+
+    /// Access token of the account sending the status
+    #[clap(long)]
+    access_token: String,
+
+    /// ID of the room to send the status to, e.g. !abcdef:matrix.org
+    #[clap(long)]
+    room_id: String,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn location(equipment: &Equipment) -> &str {
+    equipment.location().unwrap_or_default()
+}
+
+/// Send the headline of `summary` and the list of broken elevators to the Matrix room.
+/// Only the `max_listed` most important ones are listed, see [`limit_broken`].
+pub fn send_result(
+    equipments: &[Equipment],
+    summary: &StatusSummary,
+    max_listed: Option<usize>,
+    args: &MatrixArgs,
+) -> Result<(), Box<dyn Error>> {
+    let (listed, more_broken) = limit_broken(equipments, max_listed);
+    let broken: Vec<_> = listed
+        .iter()
+        .filter(|equipment| equipment.working == Some(false))
+        .collect();
+
+    let mut body = summary.headline();
+    let mut formatted_body = format!("<p><b>{}</b></p>", escape_html(&summary.headline()));
+    if !broken.is_empty() {
+        formatted_body += "<ul>";
+        for equipment in broken {
+            let emoji = if equipment.maintenance {
+                "🔧"
+            } else if equipment.recently_broken {
+                "🕒"
+            } else {
+                "⛔"
+            };
+            body += &format!(
+                "\n{emoji} Aufzug {} ({})",
+                equipment.name,
+                location(equipment)
+            );
+            formatted_body += &format!(
+                "<li>{emoji} Aufzug {} ({})</li>",
+                escape_html(&equipment.name),
+                escape_html(location(equipment))
+            );
+        }
+        if more_broken > 0 {
+            body += &format!("\n…und {more_broken} weitere außer Betrieb");
+            formatted_body += &format!("<li>…und {more_broken} weitere außer Betrieb</li>");
+        }
+        formatted_body += "</ul>";
+    }
+
+    // The transaction id only has to be unique per access token
+    let transaction_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut url = Url::parse(&args.homeserver_url)?;
+    url.path_segments_mut()
+        .map_err(|_| format!("Invalid homeserver URL: {}", args.homeserver_url))?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            &args.room_id,
+            "send",
+            "m.room.message",
+            &transaction_id.to_string(),
+        ]);
+
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?
+        .put(url)
+        .bearer_auth(&args.access_token)
+        .json(&json!({
+            "msgtype": "m.text",
+            "body": body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": formatted_body,
+        }))
+        .send()?
+        .error_for_status()?;
+
+    println!("Matrix message sent successfully!");
+    Ok(())
+}
+
+/// Sends the status to the Matrix room.
+pub struct MatrixNotifier<'a> {
+    pub args: &'a MatrixArgs,
+    /// See [`send_result`]
+    pub max_listed: Option<usize>,
+}
+
+impl Notifier for MatrixNotifier<'_> {
+    fn name(&self) -> &str {
+        "Matrix"
+    }
+
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        _errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        send_result(equipments, summary, self.max_listed, self.args)
+    }
+}
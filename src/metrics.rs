@@ -0,0 +1,156 @@
+use crate::{data_dir::DataDir, EquipmentList, EquipmentResults, Error};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error as StdError, fmt::Write};
+
+/// Where the match metrics are kept between runs.
+const METRICS_FILE: &str = "elstatus.metrics.json";
+
+/// The match metrics in the Prometheus text format, e.g. for the textfile collector of the node exporter.
+pub const PROMETHEUS_FILE: &str = "elstatus.metrics.prom";
+
+/// The number of equally wide buckets the similarity scores between 0 and 1 are counted in.
+pub const NUM_SCORE_BUCKETS: usize = 10;
+
+/// How well the searches matched over many runs, to notice when the names in the API drift
+/// away from the searches before they stop matching at all.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatchMetrics {
+    /// The number of runs recorded
+    pub runs: u64,
+    /// How many matched searches had a score in each bucket, see [`score_bucket`]
+    pub score_buckets: [u64; NUM_SCORE_BUCKETS],
+    /// The sum of all scores, for their mean
+    pub score_sum: f64,
+    /// The number of searches that couldn't be matched, by station, see [`EquipmentList::name`]
+    pub unmatched: BTreeMap<String, u64>,
+}
+
+impl MatchMetrics {
+    /// The metrics stored by the last run, or empty ones.
+    pub fn load(data_dir: &DataDir) -> Self {
+        std::fs::read_to_string(data_dir.state_file(METRICS_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store the metrics for the next run, and in the Prometheus format next to them.
+    pub fn store(&self, data_dir: &DataDir) -> Result<(), Box<dyn StdError>> {
+        std::fs::write(
+            data_dir.state_file(METRICS_FILE),
+            serde_json::to_string(self)?,
+        )?;
+        std::fs::write(data_dir.state_file(PROMETHEUS_FILE), self.to_prometheus())?;
+        Ok(())
+    }
+
+    /// Add the scores and unmatched searches of one run.
+    /// Lists that couldn't be fetched at all say nothing about how well they match and are skipped.
+    pub fn record(&mut self, lists: &[EquipmentList], results: &[Result<EquipmentResults, Error>]) {
+        self.runs += 1;
+        for (list, results) in lists.iter().zip(results) {
+            let Ok(results) = results else {
+                continue;
+            };
+            for score in results.scores.iter() {
+                self.score_buckets[score_bucket(*score)] += 1;
+                self.score_sum += f64::from(*score);
+            }
+            let unmatched = results.unmatched_queries().count() as u64;
+            if unmatched > 0 {
+                *self.unmatched.entry(list.name()).or_default() += unmatched;
+            }
+        }
+    }
+
+    /// The number of matched searches recorded.
+    pub fn num_scores(&self) -> u64 {
+        self.score_buckets.iter().sum()
+    }
+
+    /// A few lines for the logs, e.g. "📊 Match scores of 12 searches in 3 runs (mean 0.912): ...".
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        let mean = self.score_sum / self.num_scores().max(1) as f64;
+        let buckets: Vec<_> = self
+            .score_buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| format!("≤{:.1}: {}", upper_bound(bucket), count))
+            .collect();
+        write!(
+            summary,
+            "📊 Match scores of {} searches in {} runs (mean {:.3}): {}",
+            self.num_scores(),
+            self.runs,
+            mean,
+            buckets.join(", ")
+        )
+        .unwrap();
+        if !self.unmatched.is_empty() {
+            let unmatched: Vec<_> = self
+                .unmatched
+                .iter()
+                .map(|(station, count)| format!("{station}: {count}"))
+                .collect();
+            write!(
+                summary,
+                "\n📊 Unmatched searches by station: {}",
+                unmatched.join(", ")
+            )
+            .unwrap();
+        }
+        summary
+    }
+
+    /// The metrics in the Prometheus text exposition format:
+    /// the scores as the histogram `elstatus_match_score`
+    /// and the unmatched searches as the counter `elstatus_unmatched_searches_total` per station.
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        text += "# HELP elstatus_match_score Similarity of the matched equipment names to their searches\n";
+        text += "# TYPE elstatus_match_score histogram\n";
+        let mut cumulative = 0;
+        for (bucket, count) in self.score_buckets.iter().enumerate() {
+            cumulative += count;
+            writeln!(
+                text,
+                "elstatus_match_score_bucket{{le=\"{:.1}\"}} {}",
+                upper_bound(bucket),
+                cumulative
+            )
+            .unwrap();
+        }
+        writeln!(
+            text,
+            "elstatus_match_score_bucket{{le=\"+Inf\"}} {cumulative}"
+        )
+        .unwrap();
+        writeln!(text, "elstatus_match_score_sum {}", self.score_sum).unwrap();
+        writeln!(text, "elstatus_match_score_count {cumulative}").unwrap();
+
+        text += "# HELP elstatus_unmatched_searches_total Searches that couldn't be matched, by station\n";
+        text += "# TYPE elstatus_unmatched_searches_total counter\n";
+        for (station, count) in self.unmatched.iter() {
+            let station = station.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(
+                text,
+                "elstatus_unmatched_searches_total{{station=\"{station}\"}} {count}"
+            )
+            .unwrap();
+        }
+        text
+    }
+}
+
+/// The bucket a score between 0 and 1 is counted in.
+/// Like Prometheus buckets, each includes its upper bound, so a score of 0.9 is counted in the bucket up to 0.9.
+pub fn score_bucket(score: f32) -> usize {
+    ((score * NUM_SCORE_BUCKETS as f32).ceil() as usize).clamp(1, NUM_SCORE_BUCKETS) - 1
+}
+
+/// The highest score counted in `bucket`.
+fn upper_bound(bucket: usize) -> f64 {
+    (bucket + 1) as f64 / NUM_SCORE_BUCKETS as f64
+}
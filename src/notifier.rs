@@ -0,0 +1,33 @@
+use crate::{summary::StatusSummary, Equipment};
+use std::error::Error;
+
+/// A backend the status is passed on to, e.g. an email or an e-paper display.
+pub trait Notifier {
+    /// A short name for log messages, e.g. "E-Mail".
+    fn name(&self) -> &str;
+
+    /// Pass on the status of `equipments`, along with the errors that occurred while fetching them.
+    /// The `summary` is the one of the whole run, e.g. with `--unknown-as` applied,
+    /// so every backend reports the same counts as the exit code.
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>>;
+}
+
+impl<T: Notifier + ?Sized> Notifier for &T {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        (**self).notify(equipments, errors, summary)
+    }
+}
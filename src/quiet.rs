@@ -0,0 +1,82 @@
+use crate::{
+    change::{status_changes, StatusChange},
+    data_dir::DataDir,
+    Equipment,
+};
+use chrono::NaiveTime;
+use clap::Args;
+use std::error::Error;
+
+/// The equipments of the last run, to tell which elevators broke down since.
+const LAST_STATUS_FILE: &str = "elstatus.last.json";
+
+#[derive(Args, Debug, Default)]
+pub struct QuietHoursArgs {
+    /// Local time at which the quiet hours start, e.g. "22:00".
+    /// No notifications are sent until --quiet-end, the displays are still updated.
+    /// If the start is after the end, the quiet hours wrap past midnight.
+    #[arg(long, value_name = "HH:MM", requires = "quiet_end")]
+    pub quiet_start: Option<NaiveTime>,
+
+    /// Local time at which the quiet hours end, e.g. "07:00"
+    #[arg(long, value_name = "HH:MM", requires = "quiet_start")]
+    pub quiet_end: Option<NaiveTime>,
+
+    /// Notify during the quiet hours anyway if an elevator that worked during the last run is now broken
+    #[arg(long)]
+    pub notify_newly_broken: bool,
+}
+
+impl QuietHoursArgs {
+    /// Whether `time` falls into the quiet hours.
+    /// The start is inclusive and the end exclusive, so 22:00 to 07:00 is quiet at 22:00 and 03:00, but not at 07:00.
+    pub fn is_quiet(&self, time: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (self.quiet_start, self.quiet_end) else {
+            return false;
+        };
+        if start <= end {
+            start <= time && time < end
+        } else {
+            // The window wraps past midnight
+            time >= start || time < end
+        }
+    }
+
+    /// Whether notifications should be sent at `time`, given the status of the last run.
+    pub fn should_notify(
+        &self,
+        time: NaiveTime,
+        previous: Option<&[Equipment]>,
+        equipments: &[Equipment],
+    ) -> bool {
+        if !self.is_quiet(time) {
+            return true;
+        }
+        self.notify_newly_broken
+            && previous.is_some_and(|previous| newly_broken(previous, equipments))
+    }
+}
+
+/// Whether any elevator that worked in `previous` is broken in `equipments`.
+pub fn newly_broken(previous: &[Equipment], equipments: &[Equipment]) -> bool {
+    status_changes(previous, equipments)
+        .iter()
+        .any(|(change, _)| *change == StatusChange::NewlyBroken)
+}
+
+/// The equipments stored by the last run, if any.
+pub fn load_last_status(data_dir: &DataDir) -> Option<Vec<Equipment>> {
+    let json = std::fs::read_to_string(data_dir.state_file(LAST_STATUS_FILE)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn store_last_status(
+    data_dir: &DataDir,
+    equipments: &[Equipment],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(
+        data_dir.state_file(LAST_STATUS_FILE),
+        serde_json::to_string(equipments)?,
+    )?;
+    Ok(())
+}
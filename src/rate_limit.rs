@@ -0,0 +1,34 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Spaces out requests so no more than a given number per second are sent,
+/// no matter how many threads share the limiter.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// The earliest time the next request may be sent
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Block until the next request may be sent.
+    pub fn wait(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        // Sleep outside the lock, so other threads can reserve their slots meanwhile
+        std::thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
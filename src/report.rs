@@ -0,0 +1,133 @@
+use crate::{
+    email::{error_chain, errors_context},
+    group_by_station,
+    notifier::Notifier,
+    run::RunContext,
+    summary::StatusSummary,
+    Equipment,
+};
+use clap::Args;
+use std::{error::Error, path::PathBuf};
+use tera::Tera;
+
+#[derive(Args, Debug)]
+pub struct ReportArgs {
+    /// Where to write the HTML report, e.g. to be served by an internal wiki
+    #[clap(long, value_name = "FILE_PATH")]
+    report_path: PathBuf,
+
+    /// A Tera template to render the report with instead of the built-in one,
+    /// see src/templates/report.html for the available context
+    #[clap(long, value_name = "FILE_PATH")]
+    report_template: Option<PathBuf>,
+}
+
+impl ReportArgs {
+    /// Write the report to `report_path` instead of the one given on the command line,
+    /// e.g. so `--send-test` doesn't replace the real report.
+    pub fn set_report_path(&mut self, report_path: PathBuf) {
+        self.report_path = report_path;
+    }
+
+    /// Everything that is wrong with the arguments, found without rendering the report:
+    /// a template that is missing or doesn't compile, or a directory to write the report to that doesn't exist.
+    pub fn config_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(err) = template(self) {
+            problems.push(error_chain(err.as_ref()));
+        }
+        let directory = self
+            .report_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty());
+        if let Some(directory) = directory.filter(|directory| !directory.is_dir()) {
+            problems.push(format!("Directory {} not found", directory.display()));
+        }
+        problems
+    }
+}
+
+/// The name of the report template, both the built-in one and one given with `--report-template`.
+const REPORT_TEMPLATE: &str = "report.html";
+
+/// The built-in report.html template.
+/// Unlike the status email, it has all of its CSS inline in a style element, as no mail client has to support it.
+pub fn default_template() -> tera::Result<Tera> {
+    let mut tera = Tera::default();
+    tera.add_raw_template(REPORT_TEMPLATE, include_str!("templates/report.html"))?;
+    Ok(tera)
+}
+
+/// The built-in template, or the one given with `--report-template`.
+pub fn template(args: &ReportArgs) -> Result<Tera, Box<dyn Error>> {
+    match &args.report_template {
+        Some(path) => {
+            let mut tera = Tera::default();
+            tera.add_template_file(path, Some(REPORT_TEMPLATE))?;
+            Ok(tera)
+        }
+        None => Ok(default_template()?),
+    }
+}
+
+/// The context the report is rendered with: the context of the errors template, see [`errors_context`],
+/// the `summary` and its `headline`, all `equipments` and the same equipments grouped by `stations`,
+/// see [`group_by_station`]. Unlike notifications, the report always lists every elevator.
+pub fn report_context(
+    equipments: &[Equipment],
+    errors: &[Box<dyn Error>],
+    summary: &StatusSummary,
+    run: &RunContext,
+) -> tera::Context {
+    let mut context = errors_context(errors, Some(run));
+    context.insert("summary", summary);
+    context.insert("headline", &summary.headline());
+    context.insert("equipments", equipments);
+    context.insert("stations", &group_by_station(equipments));
+    context
+}
+
+/// Render the report and write it to `--report-path`, replacing the previous one.
+pub fn write_report(
+    equipments: &[Equipment],
+    errors: &[Box<dyn Error>],
+    summary: &StatusSummary,
+    run: &RunContext,
+    args: &ReportArgs,
+) -> Result<(), Box<dyn Error>> {
+    let report = template(args)?.render(
+        REPORT_TEMPLATE,
+        &report_context(equipments, errors, summary, run),
+    )?;
+    std::fs::write(&args.report_path, report).map_err(|err| {
+        format!(
+            "Could not write the report to {}: {}",
+            args.report_path.display(),
+            err
+        )
+    })?;
+    println!("📄 Report written to {}", args.report_path.display());
+    Ok(())
+}
+
+/// Writes the HTML report.
+pub struct ReportNotifier<'a> {
+    pub args: &'a ReportArgs,
+    /// Named in the footer of the report, along with when it started
+    pub run: &'a RunContext,
+}
+
+impl Notifier for ReportNotifier<'_> {
+    fn name(&self) -> &str {
+        "Report"
+    }
+
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        write_report(equipments, errors, summary, self.run, self.args)
+    }
+}
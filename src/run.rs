@@ -0,0 +1,40 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// Identifies a single run, so the status email, the display update and the log lines
+/// of the same run can be told apart from those of other runs.
+/// In the templates, it is available as `run.run_id` and `run.started_at`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunContext {
+    pub run_id: Uuid,
+    pub started_at: DateTime<Local>,
+}
+
+impl RunContext {
+    /// A new run with a random id, starting now.
+    pub fn new() -> Self {
+        Self {
+            run_id: Uuid::new_v4(),
+            started_at: Local::now(),
+        }
+    }
+}
+
+impl Default for RunContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for RunContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.run_id,
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        )
+    }
+}
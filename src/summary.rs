@@ -0,0 +1,209 @@
+use crate::{Equipment, Priority};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{collections::HashSet, error::Error};
+
+/// Which side elevators with an unknown status are counted on, for two-state alerting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnknownAs {
+    Working,
+    Broken,
+}
+
+/// How many of the monitored elevators are in which state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StatusSummary {
+    pub num_ok: usize,
+    /// Broken elevators, except those with [`Priority::Low`]
+    pub num_failed: usize,
+    /// Broken elevators with [`Priority::High`], also counted in `num_failed`
+    pub num_critical: usize,
+    /// Broken elevators with [`Priority::Low`], which are only mentioned as a notice
+    pub num_minor_failed: usize,
+    /// Broken elevators that haven't been broken for long enough to alert about them,
+    /// see [`Equipment::recently_broken`]. Not counted in `num_failed` or `num_critical`
+    pub num_recently_broken: usize,
+    /// Elevators out of service for planned maintenance, which aren't counted as broken or unknown
+    pub num_maintenance: usize,
+    /// Elevators the API reports without a working status
+    pub num_unknown: usize,
+    /// Stations or searches that couldn't be checked at all
+    pub num_fetch_errors: usize,
+}
+
+impl StatusSummary {
+    pub fn new(equipments: &[Equipment], errors: &[Box<dyn Error>]) -> Self {
+        let num_ok = equipments
+            .iter()
+            .filter(|eq| eq.working.unwrap_or_default())
+            .count();
+
+        let num_maintenance = equipments
+            .iter()
+            .filter(|eq| eq.maintenance && eq.working != Some(true))
+            .count();
+
+        let broken = || {
+            equipments
+                .iter()
+                .filter(|eq| !eq.working.unwrap_or(true) && !eq.maintenance)
+        };
+        let num_minor_failed = broken().filter(|eq| eq.priority == Priority::Low).count();
+        let num_recently_broken = broken()
+            .filter(|eq| eq.priority != Priority::Low && eq.recently_broken)
+            .count();
+        let num_failed = broken().count() - num_minor_failed - num_recently_broken;
+        let num_critical = broken()
+            .filter(|eq| eq.priority == Priority::High && !eq.recently_broken)
+            .count();
+
+        let num_unknown = equipments
+            .iter()
+            .filter(|eq| eq.working.is_none() && !eq.maintenance)
+            .count();
+
+        Self {
+            num_ok,
+            num_failed,
+            num_critical,
+            num_minor_failed,
+            num_recently_broken,
+            num_maintenance,
+            num_unknown,
+            num_fetch_errors: errors.len(),
+        }
+    }
+
+    /// Count elevators with an unknown status as working or broken instead, if `unknown_as` is set.
+    /// Used for the headline and exit code, the equipments themselves keep their unknown status.
+    pub fn with_unknown_as(mut self, unknown_as: Option<UnknownAs>) -> Self {
+        match unknown_as {
+            Some(UnknownAs::Working) => self.num_ok += self.num_unknown,
+            Some(UnknownAs::Broken) => self.num_failed += self.num_unknown,
+            None => return self,
+        }
+        self.num_unknown = 0;
+        self
+    }
+
+    /// One emoji per state that occurs, most severe first.
+    pub fn emojis(&self) -> String {
+        [
+            (self.num_critical, "🚨"),
+            (self.num_failed, "⛔"),
+            (self.num_recently_broken, "🕒"),
+            (self.num_maintenance, "🔧"),
+            (self.num_minor_failed, "ℹ️"),
+            (self.num_ok, "✅"),
+            (self.num_unknown, "❔"),
+            (self.num_fetch_errors, "⚠️"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(_, emoji)| emoji)
+        .collect()
+    }
+
+    pub fn message(&self) -> &'static str {
+        if self.num_critical > 0 {
+            return "KRITISCH: Wichtiger Aufzug defekt!";
+        }
+        if self.num_failed > 0 {
+            return "Achtung: Defekter Aufzug auf dem Weg!";
+        }
+        if self.num_recently_broken > 0 {
+            return "Aufzug kürzlich als defekt gemeldet";
+        }
+        if self.num_maintenance > 0 {
+            return "Hinweis: Aufzug in Wartung";
+        }
+        if self.num_minor_failed > 0 {
+            return "Hinweis: Aufzug mit niedriger Priorität defekt";
+        }
+        match (self.num_ok, self.num_unknown, self.num_fetch_errors) {
+            (0, 0, 0) => "Warnung: Keine Aufzüge gefunden!",
+            (0, 0, _) => "Warnung: Aufzugstatus konnte nicht geprüft werden!",
+            (0, _, _) => "Warnung: Aufzugstatus unbekannt!",
+            (_, 0, 0) => "Alle Aufzüge funktionieren!",
+            (_, _, 0) => "Kein defekter Aufzug (einige Unbekannt)!",
+            (_, 0, _) => "Kein defekter Aufzug (einige nicht geprüft)!",
+            (_, _, _) => "Kein defekter Aufzug (einige Unbekannt oder nicht geprüft)!",
+        }
+    }
+
+    /// The process exit code for this status, so scripts can branch on it:
+    ///
+    /// - 0: all elevators are working
+    /// - 1: at least one elevator is broken, not counting those with [`Priority::Low`] or in maintenance
+    /// - 2: some stations or searches could not be checked
+    /// - 3: the status of some elevators is unknown
+    ///
+    /// If several apply, the lowest non-zero code wins.
+    pub fn exit_code(&self) -> i32 {
+        if self.num_failed > 0 {
+            1
+        } else if self.num_fetch_errors > 0 {
+            2
+        } else if self.num_unknown > 0
+            || self.num_ok + self.num_recently_broken + self.num_minor_failed + self.num_maintenance
+                == 0
+        {
+            3
+        } else {
+            0
+        }
+    }
+
+    /// The emojis followed by the message, e.g. for a subject line.
+    pub fn headline(&self) -> String {
+        format!("{} {}", self.emojis(), self.message())
+    }
+}
+
+/// The `equipments` to list in a notification, with only the `max_listed` most important broken ones,
+/// and how many broken ones were left out.
+/// Broken elevators with a higher priority come first, then they are ordered by station and name.
+/// Everything else is kept, and so is the order of `equipments`.
+pub fn limit_broken(
+    equipments: &[Equipment],
+    max_listed: Option<usize>,
+) -> (Vec<Equipment>, usize) {
+    limit_listed(equipments, max_listed, |equipment| {
+        equipment.working == Some(false)
+    })
+}
+
+/// Like [`limit_broken`], but for the equipments that are listed as `broken` by the caller,
+/// e.g. the displays also list those with an unknown status.
+pub fn limit_listed(
+    equipments: &[Equipment],
+    max_listed: Option<usize>,
+    broken: impl Fn(&Equipment) -> bool,
+) -> (Vec<Equipment>, usize) {
+    let Some(max_listed) = max_listed else {
+        return (equipments.to_vec(), 0);
+    };
+    let mut broken: Vec<_> = equipments
+        .iter()
+        .enumerate()
+        .filter(|(_, equipment)| broken(equipment))
+        .collect();
+    broken.sort_by(|(_, a), (_, b)| {
+        b.priority()
+            .cmp(&a.priority())
+            .then_with(|| a.location().cmp(&b.location()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let num_more = broken.len().saturating_sub(max_listed);
+    let hidden: HashSet<_> = broken[broken.len() - num_more..]
+        .iter()
+        .map(|(index, _)| *index)
+        .collect();
+    let listed = equipments
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !hidden.contains(index))
+        .map(|(_, equipment)| equipment.clone())
+        .collect();
+    (listed, num_more)
+}
@@ -0,0 +1,27 @@
+use crate::Equipment;
+use chrono::Utc;
+use std::error::Error;
+
+/// The station all equipments of the test notification belong to,
+/// so it can't be mistaken for a real status.
+pub const TEST_STATION: &str = "ElStatus test notification";
+
+/// Fabricated equipments for `--send-test`: one working, one broken and one under maintenance,
+/// so every part of a notification is rendered.
+pub fn equipments() -> Vec<Equipment> {
+    let equipment = |name: &str, working| {
+        Equipment::new(name, "elevator", Some(working), Some("ElStatus".to_owned()))
+            .with_station(TEST_STATION)
+            .with_last_update(Utc::now())
+    };
+    vec![
+        equipment("Testaufzug Gleis 1/2", true),
+        equipment("Testaufzug Gleis 3/4", false),
+        equipment("Testaufzug Gleis 5/6", false).with_maintenance(true),
+    ]
+}
+
+/// A fabricated error for `--send-test`, so the errors are sent as well, e.g. the errors email.
+pub fn errors() -> Vec<Box<dyn Error>> {
+    vec![format!("{TEST_STATION}: This is not a real error").into()]
+}
@@ -0,0 +1,54 @@
+use crate::{ApiClient, Equipment};
+use std::{
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Keeps a [`watch_equipment`] poller running.
+/// It stops once [`WatchHandle::stop`] is called or the handle is dropped.
+pub struct WatchHandle {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl WatchHandle {
+    /// Stop polling and wait for the poller to finish a request it may be waiting on.
+    pub fn stop(self) {
+        self.stop.send(()).ok();
+        self.thread.join().ok();
+    }
+}
+
+/// Poll the equipment with the given `id` every `interval` in the background,
+/// and call `on_change` with the first status and whenever `working` changes after that,
+/// including changes from or to an unknown status.
+/// Failed requests are reported and retried at the next interval, without calling `on_change`.
+pub fn watch_equipment(
+    client: ApiClient,
+    id: impl Into<String>,
+    interval: Duration,
+    mut on_change: impl FnMut(&Equipment) + Send + 'static,
+) -> WatchHandle {
+    let id = id.into();
+    let (stop, stopped) = mpsc::channel();
+    let thread = std::thread::spawn(move || {
+        let mut working = None;
+        loop {
+            match client.get_equipment_by_id(&id) {
+                Ok(equipment) => {
+                    if working != Some(equipment.working) {
+                        working = Some(equipment.working);
+                        on_change(&equipment);
+                    }
+                }
+                Err(err) => println!("⚠️ Could not fetch equipment {}: {}", id, err),
+            }
+            // Stops both when asked to and when the handle was dropped
+            if stopped.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+        }
+    });
+    WatchHandle { stop, thread }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    notifier::Notifier, retry_with_backoff_if, structured_error, summary::StatusSummary, Equipment,
+};
+use clap::Args;
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::{error::Error, time::Duration};
+
+#[derive(Args, Debug)]
+pub struct WebhookArgs {
+    /// URL to POST the status JSON to
+    #[clap(long)]
+    url: String,
+
+    /// Additional header to send, e.g. "Authorization: Bearer <token>".
+    /// Can be given multiple times.
+    #[clap(long = "header", value_name = "NAME: VALUE")]
+    headers: Vec<String>,
+}
+
+/// Only server errors and connection problems may go away on their own.
+fn is_retryable(err: &reqwest::Error) -> bool {
+    err.status()
+        .map(|status| status.is_server_error())
+        .unwrap_or(err.is_timeout() || err.is_connect())
+}
+
+/// POST `{ summary, equipments, errors, structured_errors }` as JSON to the webhook URL.
+/// The `summary` is the one of the run, so it counts elevators like the other backends and the exit code.
+/// `errors` are the messages, `structured_errors` the same errors as [`crate::StructuredError`].
+/// Server errors are retried with exponential backoff.
+pub fn send_result(
+    equipments: &[Equipment],
+    errors: &[Box<dyn Error>],
+    summary: &StatusSummary,
+    args: &WebhookArgs,
+) -> Result<(), Box<dyn Error>> {
+    let headers = args
+        .headers
+        .iter()
+        .map(|header| {
+            header
+                .split_once(':')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .ok_or_else(|| format!("Invalid header, expected \"NAME: VALUE\": {}", header))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let body = json!({
+        "summary": summary,
+        "equipments": equipments,
+        "errors": errors.iter().map(|err| err.to_string()).collect::<Vec<_>>(),
+        "structured_errors": errors.iter().map(|err| structured_error(err.as_ref())).collect::<Vec<_>>(),
+    });
+
+    let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+    const NUM_ATTEMPTS: u32 = 5;
+    retry_with_backoff_if(
+        NUM_ATTEMPTS,
+        Duration::from_millis(500),
+        is_retryable,
+        || {
+            headers
+                .iter()
+                .fold(client.post(&args.url), |request, (name, value)| {
+                    request.header(*name, *value)
+                })
+                .json(&body)
+                .send()?
+                .error_for_status()
+        },
+    )?;
+
+    println!("Webhook called successfully!");
+    Ok(())
+}
+
+impl Notifier for WebhookArgs {
+    fn name(&self) -> &str {
+        "Webhook"
+    }
+
+    fn notify(
+        &self,
+        equipments: &[Equipment],
+        errors: &[Box<dyn Error>],
+        summary: &StatusSummary,
+    ) -> Result<(), Box<dyn Error>> {
+        send_result(equipments, errors, summary, self)
+    }
+}
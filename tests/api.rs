@@ -0,0 +1,1167 @@
+use clap::Parser;
+use elstatus::{
+    cache::ResponseCache, data_dir::DataDir, dedup_equipments, summary::StatusSummary,
+    unmatched_searches, ApiArgs, ApiClient, Equipment, EquipmentAccessError, EquipmentList,
+    EquipmentSearch, Error, Location, MatchOptions, Priority, UnmatchedSearch,
+};
+use flate2::{write::GzEncoder, Compression};
+use httpmock::{Method::GET, MockServer};
+use serde_json::json;
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+static EQUIPMENT_INFOS: &str = include_str!("fixtures/equipment-infos.json");
+
+fn client(server: &MockServer) -> ApiClient {
+    ApiClient::new("token".to_owned(), "elstatus-test", Duration::from_secs(5))
+        .unwrap()
+        .with_base_url(server.base_url())
+}
+
+fn wannsee(searches: &[&str]) -> EquipmentList {
+    searches
+        .iter()
+        .fold(
+            EquipmentList::builder()
+                .latitude(52.422207)
+                .longitude(13.181025),
+            |builder, search| builder.add_search(*search),
+        )
+        .build()
+        .unwrap()
+}
+
+fn equipment(id: Option<&str>, name: &str, working: Option<bool>) -> Equipment {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "category": "elevator",
+        "working": working,
+        "place": "Berlin-Wannsee",
+    }))
+    .unwrap()
+}
+
+#[test]
+fn matches_searches_against_the_fetched_equipment() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("appToken", "token")
+            .query_param("accuracy", "500");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2", "Gleis 3/4", "Vorplatz"]))
+        .unwrap();
+
+    mock.assert();
+    assert!(results.errors.is_empty());
+    assert_eq!(
+        results.equipments,
+        vec![
+            equipment(Some("elevator-1"), "zu Gleis 1/2 (S-Bahn)", Some(true)),
+            equipment(Some("elevator-2"), "zu Gleis 3/4 (S-Bahn)", Some(false)),
+            equipment(Some("elevator-3"), "Ausgang Vorplatz", None),
+        ]
+    );
+}
+
+#[test]
+fn shows_the_alias_of_a_search_instead_of_the_api_name() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let list: EquipmentList = serde_json::from_value(json!({
+        "latitude": 52.422207,
+        "longitude": 13.181025,
+        "equipment_searches": [
+            { "query": "Gleis 1/2", "alias": "Platform 1/2", "note": "main entrance" },
+            "Gleis 3/4",
+        ],
+    }))
+    .unwrap();
+    assert_eq!(
+        list.equipment_searches,
+        vec![
+            EquipmentSearch {
+                query: "Gleis 1/2".to_owned(),
+                alias: Some("Platform 1/2".to_owned()),
+                note: Some("main entrance".to_owned()),
+            },
+            EquipmentSearch::from("Gleis 3/4"),
+        ]
+    );
+
+    let results = client(&server).get_equipments(&list).unwrap();
+    let names: Vec<_> = results.equipments.iter().map(Equipment::name).collect();
+    assert_eq!(names, vec!["Platform 1/2", "zu Gleis 3/4 (S-Bahn)"]);
+}
+
+#[test]
+fn names_equipment_in_the_first_available_language() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200)
+            .body(include_str!("fixtures/equipment-infos-multilingual.json"));
+    });
+    let list = EquipmentList::builder()
+        .latitude(52.422207)
+        .longitude(13.181025)
+        .add_search("platform 1/2")
+        .add_search("Gleis 3/4")
+        .add_search("Vorplatz")
+        .language("en")
+        .language("de")
+        .build()
+        .unwrap();
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    assert!(results.errors.is_empty());
+    assert_eq!(
+        results.equipments,
+        vec![
+            equipment(Some("elevator-1"), "to platform 1/2 (S-Bahn)", Some(true)),
+            // No English description, so the German one is used
+            equipment(Some("elevator-2"), "zu Gleis 3/4 (S-Bahn)", Some(false)),
+            // Not translated at all
+            equipment(Some("elevator-3"), "Ausgang Vorplatz", None),
+        ]
+    );
+}
+
+#[test]
+fn equipment_list_builder_requires_coordinates() {
+    let err = EquipmentList::builder()
+        .latitude(52.422207)
+        .add_search("Gleis 1/2")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::IncompleteEquipmentList("longitude")));
+}
+
+#[test]
+fn equipment_list_builder_rejects_swapped_coordinates() {
+    let err = EquipmentList::builder()
+        .latitude(113.5)
+        .longitude(52.5)
+        .add_search("Gleis 1/2")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidCoordinates { .. }));
+    assert_eq!(
+        err.to_string(),
+        "Invalid coordinates 113.5,52.5: Latitude 113.5 is not between -90 and 90, \
+         Latitude and longitude may be swapped"
+    );
+}
+
+#[test]
+fn rejects_coordinates_out_of_range_without_asking_the_api() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let list: EquipmentList = serde_json::from_value(json!({
+        "latitude": 52.422207,
+        "longitude": 213.181025,
+        "equipment_searches": ["Gleis 1/2"],
+    }))
+    .unwrap();
+
+    let err = client(&server).get_equipments(&list).unwrap_err();
+
+    assert!(matches!(err, Error::InvalidCoordinates { .. }));
+    assert_eq!(err.to_structured().kind, "InvalidCoordinates");
+    mock.assert_hits(0);
+}
+
+#[test]
+fn fetches_the_equipment_of_a_place() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("placeInfoId", "4Bm6S2GJbgK5uGPSv")
+            .matches(|request| {
+                !request.query_params.as_ref().is_some_and(|params| {
+                    params
+                        .iter()
+                        .any(|(name, _)| name == "latitude" || name == "accuracy")
+                })
+            });
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let list: EquipmentList = serde_json::from_value(json!({
+        "place_id": "4Bm6S2GJbgK5uGPSv",
+        "equipment_searches": ["Gleis 1/2", "Hauptbahnhof Nord"],
+        // There is no radius to widen around a place
+        "accuracy_max": 4000,
+    }))
+    .unwrap();
+    assert_eq!(
+        list.location,
+        Location::Place {
+            place_id: "4Bm6S2GJbgK5uGPSv".to_owned()
+        }
+    );
+    assert_eq!(list.name(), "place 4Bm6S2GJbgK5uGPSv");
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    mock.assert_hits(1);
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-1"),
+            "zu Gleis 1/2 (S-Bahn)",
+            Some(true)
+        )]
+    );
+    assert_eq!(results.errors.len(), 1);
+}
+
+#[test]
+fn reads_the_equipment_from_a_file_without_asking_the_api() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET);
+        then.status(500);
+    });
+    let list: EquipmentList = serde_json::from_value(json!({
+        "file": concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/equipment-infos.json"),
+        "equipment_searches": ["Gleis 1/2", "Hauptbahnhof Nord"],
+    }))
+    .unwrap();
+    assert!(list.config_problems().is_empty());
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    mock.assert_hits(0);
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-1"),
+            "zu Gleis 1/2 (S-Bahn)",
+            Some(true)
+        )]
+    );
+    assert_eq!(results.errors.len(), 1);
+}
+
+#[test]
+fn reports_missing_equipment_files() {
+    let server = MockServer::start();
+    let list = EquipmentList::builder()
+        .file("missing.json")
+        .add_search("Gleis 1/2")
+        .build()
+        .unwrap();
+    assert_eq!(list.name(), "file missing.json");
+    assert_eq!(
+        list.config_problems(),
+        ["Equipment file missing.json not found"]
+    );
+
+    let err = client(&server).get_equipments(&list).unwrap_err();
+
+    assert!(matches!(err, Error::EquipmentFile { .. }));
+    assert_eq!(err.to_structured().kind, "EquipmentFile");
+}
+
+#[test]
+fn attaches_the_priority_of_the_search() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let list = EquipmentList::builder()
+        .latitude(52.422207)
+        .longitude(13.181025)
+        .add_search("Gleis 1/2")
+        .add_search_with_priority("Gleis 3/4", Priority::High)
+        .build()
+        .unwrap();
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    let priorities: Vec<_> = results.equipments.iter().map(Equipment::priority).collect();
+    assert_eq!(priorities, vec![Priority::Normal, Priority::High]);
+    assert_eq!(StatusSummary::new(&results.equipments, &[]).num_critical, 1);
+}
+
+#[test]
+fn ignores_escalators() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["zu Gleis 3/4 (S-Bahn)"]))
+        .unwrap();
+
+    // The escalator has the same name, but must never be picked
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-2"),
+            "zu Gleis 3/4 (S-Bahn)",
+            Some(false)
+        )]
+    );
+}
+
+#[test]
+fn filters_escalators_by_category_only() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200)
+            .body(include_str!("fixtures/equipment-infos-escalators.json"));
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&[
+            "=Rolltreppe Ausgang Nord",
+            "=zu Gleis 7",
+            "=Aufzug zu Gleis 8",
+        ]))
+        .unwrap();
+
+    // The names don't matter, and neither does the case of the category
+    let ids: Vec<_> = results.equipments.iter().map(Equipment::id).collect();
+    assert_eq!(ids, [Some("elevator-1"), Some("elevator-2")]);
+    assert_eq!(results.equipments[0].working(), Some(true));
+    assert_eq!(results.equipments[1].category(), "ELEVATOR");
+    assert_eq!(
+        results.unmatched_queries().collect::<Vec<_>>(),
+        ["=Aufzug zu Gleis 8"]
+    );
+}
+
+#[test]
+fn reports_searches_that_cannot_be_found() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2", "Hauptbahnhof Nord"]))
+        .unwrap();
+
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-1"),
+            "zu Gleis 1/2 (S-Bahn)",
+            Some(true)
+        )]
+    );
+    assert!(matches!(
+        results.errors.as_slice(),
+        [EquipmentAccessError::CannotFindEquipment { query_text }] if query_text == "Hauptbahnhof Nord"
+    ));
+}
+
+#[test]
+fn collects_the_unmatched_searches_of_all_lists() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("latitude", "52.422207");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("latitude", "52.5");
+        then.status(500).body("Internal Server Error");
+    });
+    let labeled = EquipmentList {
+        station_label: Some("Wannsee".to_owned()),
+        ..wannsee(&["Gleis 1/2", "Hauptbahnhof Nord"])
+    };
+    // Searches of lists that couldn't be fetched at all weren't even tried
+    let unreachable = EquipmentList {
+        location: Location::Coordinates {
+            latitude: 52.5,
+            longitude: 13.181025,
+        },
+        ..wannsee(&["Gleis 7"])
+    };
+    let lists = [
+        labeled,
+        unreachable,
+        wannsee(&["Gleis 3/4", "Potsdamer Platz"]),
+    ];
+
+    let results = client(&server).get_all_equipments(&lists, 2);
+    let unmatched = unmatched_searches(&lists, &results);
+
+    assert_eq!(
+        unmatched,
+        [
+            UnmatchedSearch {
+                station: "Wannsee".to_owned(),
+                query: "Hauptbahnhof Nord".to_owned()
+            },
+            UnmatchedSearch {
+                station: "52.422207,13.181025".to_owned(),
+                query: "Potsdamer Platz".to_owned()
+            },
+        ]
+    );
+    assert_eq!(unmatched[0].to_string(), "\"Hauptbahnhof Nord\" (Wannsee)");
+}
+
+#[test]
+fn iterates_over_equipments_and_errors_of_all_lists() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("latitude", "52.422207");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("latitude", "52.5");
+        then.status(500).body("Internal Server Error");
+    });
+    let unreachable = EquipmentList {
+        location: Location::Coordinates {
+            latitude: 52.5,
+            longitude: 13.181025,
+        },
+        ..wannsee(&["Gleis 1/2"])
+    };
+    let lists = [wannsee(&["Gleis 1/2", "Hauptbahnhof Nord"]), unreachable];
+
+    let client = client(&server);
+    let mut equipments = client.iter_equipments(&lists);
+
+    assert_eq!(
+        equipments.next().unwrap().unwrap(),
+        equipment(Some("elevator-1"), "zu Gleis 1/2 (S-Bahn)", Some(true))
+    );
+    assert!(matches!(
+        equipments.next(),
+        Some(Err(Error::Access(
+            EquipmentAccessError::CannotFindEquipment { .. }
+        )))
+    ));
+    assert!(matches!(
+        equipments.next(),
+        Some(Err(Error::Access(
+            EquipmentAccessError::HTTPRequestError { .. }
+        )))
+    ));
+    assert!(equipments.next().is_none());
+}
+
+#[test]
+fn force_refresh_skips_but_updates_the_cache() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let dir = tempfile::tempdir().unwrap();
+    let cached_client = |force_refresh| {
+        client(&server)
+            .with_cache(ResponseCache::new(dir.path(), Duration::from_secs(60)))
+            .with_force_refresh(force_refresh)
+    };
+    let list = wannsee(&["Gleis 1/2"]);
+
+    cached_client(false).get_equipments(&list).unwrap();
+    cached_client(false).get_equipments(&list).unwrap();
+    mock.assert_hits(1);
+
+    cached_client(true).get_equipments(&list).unwrap();
+    mock.assert_hits(2);
+    // The refreshed response is cached again
+    assert!(std::fs::read_dir(dir.path()).unwrap().count() > 0);
+    cached_client(false).get_equipments(&list).unwrap();
+    mock.assert_hits(2);
+}
+
+#[test]
+fn caches_the_responses_of_each_api_separately() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = wannsee(&["Gleis 1/2"]);
+    let servers = [MockServer::start(), MockServer::start()];
+    for server in &servers {
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/equipment-infos.json");
+            then.status(200).body(EQUIPMENT_INFOS);
+        });
+        client(server)
+            .with_cache(ResponseCache::new(dir.path(), Duration::from_secs(60)))
+            .get_equipments(&list)
+            .unwrap();
+        mock.assert();
+    }
+}
+
+#[test]
+fn caches_places_with_similar_ids_separately() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let dir = tempfile::tempdir().unwrap();
+    for place_id in ["place/1", "place_1"] {
+        let list: EquipmentList = serde_json::from_value(json!({
+            "place_id": place_id,
+            "equipment_searches": ["Gleis 1/2"],
+        }))
+        .unwrap();
+        client(&server)
+            .with_cache(ResponseCache::new(dir.path(), Duration::from_secs(60)))
+            .get_equipments(&list)
+            .unwrap();
+    }
+
+    mock.assert_hits(2);
+}
+
+#[test]
+fn accepts_compressed_responses() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(EQUIPMENT_INFOS.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .header_exists("accept-encoding");
+        then.status(200)
+            .header("content-encoding", "gzip")
+            .body(compressed);
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(results.equipments.len(), 1);
+}
+
+#[test]
+fn reports_error_status_codes() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(500).body("Internal Server Error");
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::HTTPRequestError { status, response_text })
+            if status.as_u16() == 500 && response_text == "Internal Server Error"
+    ));
+}
+
+#[test]
+fn rejects_responses_larger_than_the_maximum() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let err = client(&server)
+        .with_max_response_size(100)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::ResponseTooLarge { max_size: 100 })
+    ));
+
+    let limit = EQUIPMENT_INFOS.len() as u64;
+    let results = client(&server)
+        .with_max_response_size(limit)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap();
+    assert_eq!(results.equipments.len(), 1);
+}
+
+#[test]
+fn limits_compressed_responses_after_decompression() {
+    let server = MockServer::start();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(EQUIPMENT_INFOS.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+    assert!(compressed.len() < 500);
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200)
+            .header("Content-Encoding", "gzip")
+            .body(compressed);
+    });
+
+    let err = client(&server)
+        .with_max_response_size(500)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::ResponseTooLarge { max_size: 500 })
+    ));
+}
+
+#[test]
+fn reports_responses_that_are_not_utf8() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200)
+            .header("Content-Type", "image/png")
+            .body([0x89, b'P', b'N', b'G', 0xff, 0x00]);
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "The response of type image/png is not valid UTF-8, it starts with: 89504e47ff00"
+    );
+    assert_eq!(err.to_structured().kind, "InvalidEncoding");
+}
+
+#[test]
+fn reports_rejected_app_tokens() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(401).body("Unauthorized");
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    // Trying again won't fix the token
+    mock.assert_hits(1);
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::Unauthorized { status }) if status.as_u16() == 401
+    ));
+    assert!(err.to_string().contains("check your WHEELMAP_TOKEN"));
+}
+
+#[test]
+fn retries_rate_limited_requests_after_the_requested_delay() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(429)
+            .header("Retry-After", "0")
+            .body("Too Many Requests");
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    // The first attempt and three retries
+    mock.assert_hits(4);
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::RateLimited)
+    ));
+    assert!(err.to_string().contains("rate limited"));
+}
+
+#[test]
+fn limits_the_request_rate() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let lists: Vec<_> = (0..4).map(|_| wannsee(&["Gleis 1/2"])).collect();
+
+    let started = Instant::now();
+    let results = client(&server)
+        .with_rate_limit(10.0)
+        .get_all_equipments(&lists, 4);
+
+    mock.assert_hits(4);
+    assert!(results.iter().all(Result::is_ok));
+    // The first request goes out right away, the others 100 ms apart
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn reports_responses_without_features() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(r#"{"type": "FeatureCollection"}"#);
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::MissingValue(..))
+    ));
+}
+
+#[test]
+fn reports_responses_with_empty_features() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200)
+            .body(r#"{"type": "FeatureCollection", "features": []}"#);
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "No elevators found near 52.422207,13.181025"
+    );
+    assert!(matches!(
+        err,
+        Error::Access(EquipmentAccessError::NoEquipmentFound { .. })
+    ));
+}
+
+#[test]
+fn rejects_invalid_json() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body("<html>Maintenance</html>");
+    });
+
+    let err = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Json(_)));
+}
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    api: ApiArgs,
+}
+
+#[test]
+fn api_url_can_be_overridden() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let base_url = server.base_url();
+    let cli = Cli::parse_from(["elstatus", "--app-token", "token", "--api-url", &base_url]);
+    let results = cli
+        .api
+        .client(&DataDir::default())
+        .unwrap()
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-1"),
+            "zu Gleis 1/2 (S-Bahn)",
+            Some(true)
+        )]
+    );
+}
+
+#[test]
+fn lists_all_equipment_including_escalators() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("accuracy", "200");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .list_equipment_at(52.422207, 13.181025, 200)
+        .unwrap();
+
+    assert!(results.errors.is_empty());
+    let categories: Vec<_> = results.equipments.iter().map(Equipment::category).collect();
+    assert_eq!(
+        categories,
+        vec!["elevator", "elevator", "escalator", "elevator"]
+    );
+}
+
+#[test]
+fn lists_the_equipment_around_features_that_cannot_be_parsed() {
+    let mut json: serde_json::Value = serde_json::from_str(EQUIPMENT_INFOS).unwrap();
+    json["features"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({ "_id": "broken-feature" }));
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(json.to_string());
+    });
+
+    let results = client(&server)
+        .list_equipment_at(52.422207, 13.181025, 200)
+        .unwrap();
+
+    assert_eq!(results.equipments.len(), 4);
+    assert_eq!(results.errors.len(), 1);
+}
+
+#[test]
+fn fetches_the_raw_response_unchanged() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let json = client(&server)
+        .fetch_raw(52.422207, 13.181025, 200)
+        .unwrap();
+
+    assert_eq!(
+        json,
+        serde_json::from_str::<serde_json::Value>(EQUIPMENT_INFOS).unwrap()
+    );
+}
+
+fn feature(name: &str, working: bool) -> serde_json::Value {
+    json!({
+        "properties": {
+            "category": "elevator",
+            "isWorking": working,
+            "description": name,
+            "placeInfoName": "Berlin-Wannsee",
+        }
+    })
+}
+
+#[test]
+fn matches_short_names_with_a_lower_arity() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).json_body(json!({
+            "features": [feature("Gleis 5", true), feature("Gleis 6", false), feature("Zugang Nord", true)],
+        }));
+    });
+    let list = |matching| {
+        EquipmentList::builder()
+            .latitude(52.422207)
+            .longitude(13.181025)
+            .add_search("G5")
+            .matching(matching)
+            .build()
+            .unwrap()
+    };
+
+    // "G5" shares too few bigrams with "Gleis 5"
+    let results = client(&server)
+        .get_equipments(&list(MatchOptions::default()))
+        .unwrap();
+    assert!(results.equipments.is_empty());
+    assert_eq!(results.errors.len(), 1);
+
+    let results = client(&server)
+        .get_equipments(&list(MatchOptions {
+            arity: 1,
+            ..MatchOptions::default()
+        }))
+        .unwrap();
+    assert!(results.errors.is_empty());
+    assert_eq!(results.equipments[0].name(), "Gleis 5");
+}
+
+#[test]
+fn follows_pages_until_all_features_are_fetched() {
+    let server = MockServer::start();
+    let first_page = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("skip", "0");
+        then.status(200).json_body(json!({
+            "totalFeatureCount": 3,
+            "features": [feature("Gleis 1/2", true), feature("Gleis 3/4", true)],
+        }));
+    });
+    let second_page = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("skip", "2");
+        then.status(200).json_body(json!({
+            "totalFeatureCount": 3,
+            "features": [feature("Ausgang Vorplatz", false)],
+        }));
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2", "Vorplatz"]))
+        .unwrap();
+
+    first_page.assert();
+    second_page.assert();
+    assert_eq!(
+        results.equipments,
+        vec![
+            equipment(None, "Gleis 1/2", Some(true)),
+            equipment(None, "Ausgang Vorplatz", Some(false)),
+        ]
+    );
+}
+
+#[test]
+fn exact_searches_skip_fuzzy_matching() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["=ZU GLEIS 1/2 (S-Bahn)", "=Gleis 1/2"]))
+        .unwrap();
+
+    assert_eq!(
+        results.equipments,
+        vec![equipment(
+            Some("elevator-1"),
+            "zu Gleis 1/2 (S-Bahn)",
+            Some(true)
+        )]
+    );
+    assert!(matches!(
+        results.errors.as_slice(),
+        [EquipmentAccessError::CannotFindEquipment { query_text }] if query_text == "=Gleis 1/2"
+    ));
+}
+
+#[test]
+fn attaches_the_station_label() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let list = EquipmentList {
+        station_label: Some("Wannsee".to_owned()),
+        ..wannsee(&["Gleis 1/2"])
+    };
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    let expected: Equipment = serde_json::from_value(json!({
+        "id": "elevator-1",
+        "name": "zu Gleis 1/2 (S-Bahn)",
+        "category": "elevator",
+        "working": true,
+        "place": "Berlin-Wannsee",
+        "station": "Wannsee",
+    }))
+    .unwrap();
+    assert_eq!(results.equipments, vec![expected]);
+}
+
+#[test]
+fn counts_elevators_of_overlapping_lists_once() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let neighbour = EquipmentList {
+        location: Location::Coordinates {
+            latitude: 52.43,
+            longitude: 13.181025,
+        },
+        ..wannsee(&["Gleis 3/4"])
+    };
+    let lists = [wannsee(&["Gleis 1/2", "Gleis 3/4"]), neighbour];
+
+    let equipments = client(&server)
+        .get_all_equipments(&lists, 2)
+        .into_iter()
+        .flat_map(|results| results.unwrap().equipments)
+        .collect();
+    let equipments = dedup_equipments(equipments);
+
+    assert_eq!(
+        equipments,
+        vec![
+            equipment(Some("elevator-1"), "zu Gleis 1/2 (S-Bahn)", Some(true)),
+            equipment(Some("elevator-2"), "zu Gleis 3/4 (S-Bahn)", Some(false)),
+        ]
+    );
+    assert_eq!(StatusSummary::new(&equipments, &[]).num_failed, 1);
+}
+
+#[test]
+fn keeps_the_raw_feature_if_requested() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let results = client(&server)
+        .with_keep_raw(true)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap();
+    let raw = results.equipments[0].raw().unwrap();
+    assert_eq!(raw["properties"]["placeInfoName"], "Berlin-Wannsee");
+
+    let results = client(&server)
+        .get_equipments(&wannsee(&["Gleis 1/2"]))
+        .unwrap();
+    assert!(results.equipments[0].raw().is_none());
+}
+
+#[test]
+fn validates_searches_with_their_scores() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+
+    let validations = client(&server)
+        .validate_searches(&wannsee(&[
+            "=Ausgang Vorplatz",
+            "Gleis 1/2",
+            "Hauptbahnhof Nord",
+        ]))
+        .unwrap();
+
+    let searches: Vec<_> = validations.iter().map(|v| v.search.as_str()).collect();
+    assert_eq!(
+        searches,
+        ["=Ausgang Vorplatz", "Gleis 1/2", "Hauptbahnhof Nord"]
+    );
+    assert_eq!(validations[0].score, Some(1.0));
+    assert!(matches!(validations[1].score, Some(score) if score > 0.4 && score < 1.0));
+    assert!(validations[1].result.is_ok());
+    assert!(validations[2].result.is_err());
+    assert_eq!(validations[2].score, None);
+}
+
+#[test]
+fn widens_the_radius_until_all_searches_match() {
+    let server = MockServer::start();
+    let narrow = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("accuracy", "500");
+        then.status(200).json_body(json!({
+            "type": "FeatureCollection",
+            "features": [feature("Gleis 1/2", true)],
+        }));
+    });
+    let wide = server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos.json")
+            .query_param("accuracy", "1000");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let list = EquipmentList {
+        accuracy_max: Some(4000),
+        ..wannsee(&["Gleis 1/2", "Vorplatz"])
+    };
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    narrow.assert();
+    wide.assert();
+    assert!(results.errors.is_empty());
+    assert_eq!(
+        results.equipments,
+        vec![
+            equipment(Some("elevator-1"), "zu Gleis 1/2 (S-Bahn)", Some(true)),
+            equipment(Some("elevator-3"), "Ausgang Vorplatz", None),
+        ]
+    );
+}
+
+#[test]
+fn stops_widening_at_the_maximum_accuracy() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let list = EquipmentList {
+        accuracy_max: Some(1500),
+        ..wannsee(&["Hauptbahnhof Nord"])
+    };
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    // 500, 1000 and finally 1500 meters
+    mock.assert_hits(3);
+    assert_eq!(results.errors.len(), 1);
+}
+
+#[test]
+fn widens_up_to_the_largest_maximum_accuracy() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos.json");
+        then.status(200).body(EQUIPMENT_INFOS);
+    });
+    let list = EquipmentList {
+        accuracy_max: Some(u32::MAX),
+        ..wannsee(&["Hauptbahnhof Nord"])
+    };
+
+    let results = client(&server).get_equipments(&list).unwrap();
+
+    // 500 meters doubled 23 times, then the maximum without overflowing
+    mock.assert_hits(25);
+    assert_eq!(results.errors.len(), 1);
+}
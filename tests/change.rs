@@ -0,0 +1,36 @@
+use elstatus::{
+    change::{status_changes, StatusChange},
+    Equipment,
+};
+use serde_json::json;
+
+fn wannsee(working: [Option<bool>; 3]) -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": working[0], "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": working[1], "place": "Berlin-Wannsee" },
+        { "name": "Ausgang Vorplatz", "category": "elevator", "working": working[2], "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn classifies_changes_in_both_directions() {
+    let previous = wannsee([Some(true), Some(false), None]);
+    let current = wannsee([Some(false), Some(true), Some(false)]);
+
+    let changes: Vec<_> = status_changes(&previous, &current)
+        .into_iter()
+        .map(|(change, equipment)| (change, equipment.name()))
+        .collect();
+
+    // Ausgang Vorplatz had an unknown status before, so it didn't break down since
+    assert_eq!(
+        changes,
+        vec![
+            (StatusChange::NewlyBroken, "Gleis 1/2"),
+            (StatusChange::BackInService, "Gleis 3/4"),
+        ]
+    );
+    assert!(status_changes(&current, &current).is_empty());
+    assert!(status_changes(&[], &current).is_empty());
+}
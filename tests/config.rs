@@ -0,0 +1,81 @@
+use elstatus::config::{expand_env, expand_env_in, section_args, EnvError, SectionError};
+use serde_json::json;
+
+#[test]
+fn expands_environment_variables() {
+    std::env::set_var("ELSTATUS_TEST_STATION", "Berlin-Wannsee");
+    std::env::set_var("ELSTATUS_TEST_PLATFORM", "1/2");
+
+    assert_eq!(
+        expand_env("${ELSTATUS_TEST_STATION}, Gleis ${ELSTATUS_TEST_PLATFORM}").unwrap(),
+        "Berlin-Wannsee, Gleis 1/2"
+    );
+    assert_eq!(expand_env("Gleis $1 {2}").unwrap(), "Gleis $1 {2}");
+
+    let mut config = json!({
+        "station_label": "${ELSTATUS_TEST_STATION}",
+        "equipment_searches": ["Gleis ${ELSTATUS_TEST_PLATFORM}"],
+        "latitude": 52.422207,
+    });
+    expand_env_in(&mut config).unwrap();
+    assert_eq!(
+        config,
+        json!({
+            "station_label": "Berlin-Wannsee",
+            "equipment_searches": ["Gleis 1/2"],
+            "latitude": 52.422207,
+        })
+    );
+}
+
+#[test]
+fn names_unset_environment_variables() {
+    std::env::remove_var("ELSTATUS_TEST_UNSET");
+
+    let err = expand_env("Bearer ${ELSTATUS_TEST_UNSET}").unwrap_err();
+    assert!(matches!(&err, EnvError::Unset { name } if name == "ELSTATUS_TEST_UNSET"));
+    assert_eq!(
+        err.to_string(),
+        "Environment variable ELSTATUS_TEST_UNSET is not set"
+    );
+
+    assert!(matches!(
+        expand_env("${ELSTATUS_TEST_UNSET"),
+        Err(EnvError::Unterminated { .. })
+    ));
+}
+
+#[test]
+fn turns_sections_into_options() {
+    let section = json!({
+        "smtp_server": "smtp.example.org",
+        "cc": ["a@example.org", "b@example.org"],
+        "attach_image": true,
+        "quiet": false,
+        "smtp_port": 587,
+        "smtp_user": "elstatus@example.org",
+    });
+
+    assert_eq!(
+        section_args("email", &section, &["smtp-user".to_owned()]).unwrap(),
+        [
+            "--attach-image",
+            "--cc",
+            "a@example.org",
+            "--cc",
+            "b@example.org",
+            "--smtp-port",
+            "587",
+            "--smtp-server",
+            "smtp.example.org",
+        ]
+    );
+    assert!(matches!(
+        section_args("email", &json!(["--smtp-server"]), &[]),
+        Err(SectionError::NotAnObject { .. })
+    ));
+    assert!(matches!(
+        section_args("email", &json!({ "smtp_server": { "host": "smtp" } }), &[]),
+        Err(SectionError::InvalidValue { .. })
+    ));
+}
@@ -0,0 +1,121 @@
+use std::process::{Command, Output};
+
+fn config_check(list: &str, args: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("equipments.json"), list).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .arg("config-check")
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap()
+}
+
+const EMAIL_ARGS: [&str; 10] = [
+    "--status-address",
+    "status@example.org",
+    "--errors-address",
+    "errors@example.org",
+    "--smtp-server",
+    "smtp.example.org",
+    "--smtp-user",
+    "elstatus@example.org",
+    "--smtp-password",
+    "secret",
+];
+
+const DISPLAY_ARGS: [&str; 6] = [
+    "--ap-address",
+    "192.168.1.2",
+    "--main-tag",
+    "00:00:02:1C:4B:3F:3B:12",
+    "--secondary-tag",
+    "0000021C4B3F3B13",
+];
+
+#[test]
+fn accepts_a_valid_config() {
+    let list = r#"[{ "latitude": 52.422207, "longitude": 13.181025, "equipment_searches": ["Gleis 1/2"] }]"#;
+    let output = config_check(list, &[&EMAIL_ARGS[..], &DISPLAY_ARGS[..]].concat());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ E-Mail"), "{stdout}");
+    assert!(stdout.contains("✅ Display"), "{stdout}");
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+}
+
+#[test]
+fn reports_every_problem_at_once() {
+    // Latitude and longitude are swapped and out of range
+    let list = r#"[{ "latitude": 13.181025, "longitude": 252.422207, "station_label": "Berlin-Wannsee",
+        "equipment_searches": ["Gleis 1/2"], "priorities": { "Gleis 3/4": "high" } }]"#;
+    let mut args = [&EMAIL_ARGS[..], &DISPLAY_ARGS[..]].concat();
+    args[1] = "status.example.org";
+    // The same MAC as the main tag, written differently
+    args[15] = "0000021c4b3f3b12";
+    args.extend(["--template", "all_clear.txt=missing.txt"]);
+    let output = config_check(list, &args);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for problem in [
+        "❌ Berlin-Wannsee: Longitude 252.42",
+        "❌ Berlin-Wannsee: Priority for unknown search \"Gleis 3/4\"",
+        "❌ E-Mail: Invalid status address status.example.org",
+        "❌ E-Mail: Template all_clear.txt not found at missing.txt",
+        "❌ Display: The main and the secondary tag are the same",
+    ] {
+        assert!(stdout.contains(problem), "{problem} missing in:\n{stdout}");
+    }
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn names_unset_environment_variables() {
+    let list = r#"[{ "latitude": 52.422207, "longitude": 13.181025,
+        "station_label": "${ELSTATUS_CONFIG_CHECK_UNSET}", "equipment_searches": ["Gleis 1/2"] }]"#;
+    let output = config_check(list, &[]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "❌ Elevator list: Environment variable ELSTATUS_CONFIG_CHECK_UNSET is not set"
+        ),
+        "{stdout}"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn reads_backend_options_from_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("equipments.json"),
+        r#"[{ "latitude": 52.422207, "longitude": 13.181025, "equipment_searches": ["Gleis 1/2"] }]"#,
+    )
+    .unwrap();
+    std::env::set_var("ELSTATUS_CONFIG_SMTP_PASSWORD", "secret");
+    std::fs::write(
+        dir.path().join("elstatus.json"),
+        r#"{
+            "email": { "status_address": "status.example.org", "errors_address": "errors@example.org",
+                "smtp_server": "smtp.example.org", "smtp_user": "elstatus@example.org",
+                "smtp_password": "${ELSTATUS_CONFIG_SMTP_PASSWORD}" },
+            "display": { "ap_address": "192.168.1.2", "main_tag": "00:00:02:1C:4B:3F:3B:12",
+                "secondary_tag": "0000021C4B3F3B13" }
+        }"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--config", "elstatus.json", "config-check"])
+        // Overrides the invalid address in the file
+        .args(["--status-address", "status@example.org"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ E-Mail"), "{stdout}");
+    assert!(stdout.contains("✅ Display"), "{stdout}");
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+}
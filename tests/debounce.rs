@@ -0,0 +1,133 @@
+use chrono::{DateTime, Duration, Utc};
+use elstatus::{
+    debounce::{DebounceArgs, StatusHistory},
+    Equipment,
+};
+use serde_json::json;
+
+fn gleis_1(working: bool) -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": working, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap()
+}
+
+fn reported(
+    history: &mut StatusHistory,
+    working: bool,
+    now: DateTime<Utc>,
+    args: &DebounceArgs,
+) -> Vec<Equipment> {
+    history.debounce(&gleis_1(working), now, args)
+}
+
+#[test]
+fn holds_back_changes_until_the_minimum_age() {
+    let args = DebounceArgs {
+        min_status_age: Some(std::time::Duration::from_secs(10 * 60)),
+        max_changes_per_hour: None,
+        broken_min_duration: None,
+    };
+    let start = Utc::now();
+    let mut history = StatusHistory::default();
+
+    assert_eq!(reported(&mut history, true, start, &args), gleis_1(true));
+    let broken_at = start + Duration::minutes(5);
+    assert_eq!(
+        reported(&mut history, false, broken_at, &args),
+        gleis_1(true)
+    );
+    assert_eq!(
+        reported(
+            &mut history,
+            false,
+            broken_at + Duration::minutes(10),
+            &args
+        ),
+        gleis_1(false)
+    );
+}
+
+#[test]
+fn keeps_the_history_of_elevators_with_the_same_name_apart() {
+    let args = DebounceArgs {
+        min_status_age: Some(std::time::Duration::from_secs(10 * 60)),
+        max_changes_per_hour: None,
+        broken_min_duration: None,
+    };
+    let aufzuege = |first: bool, second: bool| -> Vec<Equipment> {
+        serde_json::from_value(json!([
+            { "id": "aufzug-1", "name": "Aufzug", "category": "elevator", "working": first, "place": "Berlin-Wannsee" },
+            { "id": "aufzug-2", "name": "Aufzug", "category": "elevator", "working": second, "place": "Berlin-Wannsee" },
+        ]))
+        .unwrap()
+    };
+    let start = Utc::now();
+    let mut history = StatusHistory::default();
+
+    assert_eq!(
+        history.debounce(&aufzuege(true, false), start, &args),
+        aufzuege(true, false)
+    );
+    // Neither elevator changed its status, so nothing is held back
+    assert_eq!(
+        history.debounce(&aufzuege(true, false), start + Duration::minutes(5), &args),
+        aufzuege(true, false)
+    );
+}
+
+#[test]
+fn ignores_flapping_sensors() {
+    let args = DebounceArgs {
+        min_status_age: None,
+        max_changes_per_hour: Some(2),
+        broken_min_duration: None,
+    };
+    let start = Utc::now();
+    let mut history = StatusHistory::default();
+
+    assert_eq!(reported(&mut history, true, start, &args), gleis_1(true));
+    assert_eq!(
+        reported(&mut history, false, start + Duration::minutes(5), &args),
+        gleis_1(false)
+    );
+    assert_eq!(
+        reported(&mut history, true, start + Duration::minutes(10), &args),
+        gleis_1(true)
+    );
+    // The third change within an hour is held back
+    assert_eq!(
+        reported(&mut history, false, start + Duration::minutes(15), &args),
+        gleis_1(true)
+    );
+    // Once the old changes are more than an hour ago, the status is reported again
+    assert_eq!(
+        reported(&mut history, false, start + Duration::minutes(70), &args),
+        gleis_1(false)
+    );
+}
+
+#[test]
+fn lists_recently_broken_elevators_separately() {
+    let args = DebounceArgs {
+        min_status_age: None,
+        max_changes_per_hour: None,
+        broken_min_duration: Some(std::time::Duration::from_secs(30 * 60)),
+    };
+    let start = Utc::now();
+    let mut history = StatusHistory::default();
+
+    reported(&mut history, true, start, &args);
+    let broken_at = start + Duration::minutes(5);
+    let equipments = reported(&mut history, false, broken_at, &args);
+    assert_eq!(
+        history.recently_broken(&equipments, broken_at, &args),
+        vec![&gleis_1(false)[0]]
+    );
+
+    let later = broken_at + Duration::minutes(30);
+    let equipments = reported(&mut history, false, later, &args);
+    assert!(history
+        .recently_broken(&equipments, later, &args)
+        .is_empty());
+}
@@ -0,0 +1,676 @@
+use chrono::{FixedOffset, Local, TimeZone};
+use clap::Parser;
+use elstatus::{
+    data_dir::DataDir,
+    display::{
+        broken_places, draw_qr_code, format_last_update, image_hash, most_critical, parse_mac,
+        qr_code_image, to_monochrome, upload_image, verify_upload, wheelmap_url, write_images,
+        DisplayArgs, DisplayRenderer, ImageEncoding, Monochrome, SortOrder, UploadEndpoint,
+        UploadRetries, DEFAULT_DATE_FORMAT,
+    },
+    display::{
+        forget_state, load_state, shown_as_broken, update_since, DisplayNotifier, UpdatedImages,
+    },
+    notifier::Notifier,
+    run::RunContext,
+    summary::{StatusSummary, UnknownAs},
+    Equipment,
+};
+use httpmock::{
+    Method::{GET, POST},
+    MockServer,
+};
+use image::{Rgb, RgbImage};
+use serde_json::json;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[test]
+fn confirms_tags_that_picked_up_the_image() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/get_db")
+            .query_param("mac", "0000021EDEADBEEF");
+        then.status(200).json_body(json!({
+            "tags": [{ "mac": "0000021EDEADBEEF", "lastseen": now() + 1, "pending": false }]
+        }));
+    });
+
+    verify_upload(
+        &server.address().to_string(),
+        "00:00:02:1e:de:ad:be:ef",
+        SystemTime::now(),
+        Duration::from_secs(1),
+        false,
+    )
+    .unwrap();
+
+    mock.assert();
+}
+
+#[test]
+fn fails_for_tags_that_did_not_check_in() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/get_db");
+        then.status(200).json_body(json!({
+            "tags": [{ "mac": "0000021EDEADBEEF", "lastseen": now() - 600, "pending": true }]
+        }));
+    });
+
+    let err = verify_upload(
+        &server.address().to_string(),
+        "0000021EDEADBEEF",
+        SystemTime::now(),
+        Duration::from_secs(1),
+        false,
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("may be offline"));
+}
+
+#[test]
+fn formats_the_last_update_with_leading_zeros() {
+    let time = FixedOffset::east_opt(3600)
+        .unwrap()
+        .with_ymd_and_hms(2024, 1, 5, 9, 3, 0)
+        .unwrap();
+
+    assert_eq!(
+        format_last_update(&time, DEFAULT_DATE_FORMAT),
+        "05.01. - 09:03"
+    );
+    assert_eq!(format_last_update(&time, "%H:%M"), "09:03");
+}
+
+#[test]
+fn draws_a_qr_code_linking_to_the_station() {
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        {
+            "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee",
+            "coordinates": [13.18103, 52.42221],
+        },
+    ]))
+    .unwrap();
+    let url = wheelmap_url(&equipments).unwrap();
+    assert_eq!(
+        url,
+        "https://wheelmap.org/?lat=52.42221&lon=13.18103&zoom=18"
+    );
+
+    let mut image = RgbImage::from_pixel(296, 128, Rgb([255, 255, 255]));
+    draw_qr_code(&mut image, &url).unwrap();
+
+    let dark = |x, y| image.get_pixel(x, y) == &Rgb([0, 0, 0]);
+    let dark_pixels: Vec<_> = (0..296)
+        .flat_map(|x| (0..128).map(move |y| (x, y)))
+        .filter(|&(x, y)| dark(x, y))
+        .collect();
+    // The code stays in the bottom right corner, with a white border to the edges
+    assert!(dark_pixels
+        .iter()
+        .all(|&(x, y)| x >= 296 - 128 && y >= 128 - 80));
+    assert!(!dark(295, 127));
+    // The finder pattern in the top left corner of the code is dark
+    let (left, top) = dark_pixels.iter().min().copied().unwrap();
+    assert!(dark(left + 1, top + 1));
+}
+
+#[test]
+fn keeps_the_list_clear_of_the_qr_code() {
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        {
+            "name": "Zugang Nord über die Mittelpassarelle zu Gleis 1/2", "category": "elevator", "working": false,
+            "place": "Berlin Hauptbahnhof, Ausgang Europaplatz", "coordinates": [13.18103, 52.42221],
+        },
+        { "name": "Zugang Süd über die Mittelpassarelle zu Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin Hauptbahnhof, Ausgang Europaplatz" },
+        { "name": "Zugang Ost über die Mittelpassarelle zu Gleis 5/6", "category": "elevator", "working": false, "place": "Berlin Hauptbahnhof, Ausgang Europaplatz" },
+        { "name": "Zugang West über die Mittelpassarelle zu Gleis 7/8", "category": "elevator", "working": false, "place": "Berlin Hauptbahnhof, Ausgang Europaplatz" },
+    ]))
+    .unwrap();
+    let qr_code = qr_code_image(&wheelmap_url(&equipments).unwrap()).unwrap();
+    let size = qr_code.width();
+    let updated_at = Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap();
+
+    for large_text in [false, true] {
+        let (_, image) = DisplayRenderer::new()
+            .with_qr_code(true)
+            .with_large_text(large_text)
+            .render(&equipments, &updated_at);
+
+        // Below the header, nothing is drawn into the columns of the code
+        let (left, top) = (296 - size, 128 - size);
+        for x in left..296 {
+            for y in 30..top {
+                assert_eq!(image.get_pixel(x, y), &Rgb([255, 255, 255]), "{x}, {y}");
+            }
+        }
+        for (x, y, pixel) in qr_code.enumerate_pixels() {
+            assert_eq!(image.get_pixel(left + x, top + y), pixel);
+        }
+    }
+}
+
+#[test]
+fn singles_out_the_most_critical_broken_elevator() {
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": true, "priority": "high" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "priority": "low" },
+        { "name": "Zugang Nord", "category": "elevator", "working": false },
+        { "name": "Zugang Süd", "category": "elevator", "working": false },
+    ]))
+    .unwrap();
+    // Working elevators are never critical, ties go to the first one listed
+    assert_eq!(
+        most_critical(&equipments, SortOrder::Station)
+            .unwrap()
+            .name(),
+        "Zugang Nord"
+    );
+    assert!(most_critical(&equipments[..1], SortOrder::Station).is_none());
+}
+
+#[test]
+fn singles_out_the_first_critical_elevator_as_listed() {
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Zugang Süd", "category": "elevator", "working": false, "place": "Potsdam Hbf" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Potsdam Hbf" },
+    ]))
+    .unwrap();
+
+    let critical = |sort| most_critical(&equipments, sort).unwrap().name();
+    assert_eq!(critical(SortOrder::Station), "Gleis 3/4");
+    // Listed under Potsdam Hbf, which comes first with the name of Gleis 1/2
+    assert_eq!(critical(SortOrder::Name), "Gleis 1/2");
+}
+
+#[test]
+fn sorts_the_broken_elevators() {
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Potsdam Hbf" },
+        { "name": "Gleis 1/2", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+        { "name": "Zugang Süd", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 1", "category": "elevator", "working": null, "place": "Potsdam Hbf", "priority": "high" },
+        { "name": "Zugang Nord", "category": "elevator", "working": false, "place": "Berlin-Wannsee", "priority": "low" },
+    ]))
+    .unwrap();
+    let places = |sort| -> Vec<(String, Vec<String>)> { broken_places(&equipments, sort) };
+    let place = |place: &str, names: &[&str]| {
+        (
+            place.to_owned(),
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    assert_eq!(
+        places(SortOrder::Station),
+        [
+            place("Berlin-Wannsee", &["Zugang Nord", "Zugang Süd"]),
+            place("Potsdam Hbf", &["Gleis 1", "Gleis 3/4"]),
+        ]
+    );
+    assert_eq!(
+        places(SortOrder::Name),
+        [
+            place("Potsdam Hbf", &["Gleis 1", "Gleis 3/4"]),
+            place("Berlin-Wannsee", &["Zugang Nord", "Zugang Süd"]),
+        ]
+    );
+    assert_eq!(
+        places(SortOrder::Priority),
+        [
+            place("Potsdam Hbf", &["Gleis 1", "Gleis 3/4"]),
+            place("Berlin-Wannsee", &["Zugang Süd", "Zugang Nord"]),
+        ]
+    );
+}
+
+#[test]
+fn uploads_to_a_configured_endpoint() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/upload_image")
+            .body_contains(r#"name="tag""#)
+            .body_contains("0000021EDEADBEEF")
+            .body_contains(r#"name="dithering""#)
+            .body_contains(r#"name="elstatus-upload-test.jpg""#);
+        then.status(200);
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let image_path = dir.path().join("elstatus-upload-test.jpg");
+    std::fs::write(&image_path, b"not really a jpeg").unwrap();
+    let endpoint = UploadEndpoint {
+        path: "/upload_image".to_owned(),
+        mac_field: "tag".to_owned(),
+        dither_field: "dithering".to_owned(),
+    };
+    // An address with a scheme is used as is
+    upload_image(
+        &server.base_url(),
+        "0000021EDEADBEEF",
+        &image_path,
+        &endpoint,
+        &UploadRetries::default(),
+        Duration::from_secs(5),
+        false,
+    )
+    .unwrap();
+
+    mock.assert();
+}
+
+#[test]
+fn retries_failed_uploads_as_configured() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/imgupload");
+        then.status(500);
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let image_path = dir.path().join("elstatus.jpg");
+    std::fs::write(&image_path, b"not really a jpeg").unwrap();
+    let upload = |retries| {
+        upload_image(
+            &server.base_url(),
+            "0000021EDEADBEEF",
+            &image_path,
+            &UploadEndpoint::default(),
+            &UploadRetries {
+                retries,
+                delay: Duration::from_millis(1),
+            },
+            Duration::from_secs(5),
+            false,
+        )
+    };
+
+    assert!(upload(2).is_err());
+    mock.assert_hits(3);
+    // Without retries, the first failure is final
+    assert!(upload(0).is_err());
+    mock.assert_hits(4);
+}
+
+#[test]
+fn hashes_images_by_their_content() {
+    let image = |color| RgbImage::from_pixel(2, 2, Rgb([color, color, color]));
+    let encoding = ImageEncoding::default();
+
+    let hash = image_hash(&image(255), &encoding);
+    // The hash must not change between versions, as it is compared with the one stored by the last run
+    assert_eq!(
+        hash,
+        "9492fb0158d55d442d3748c9b078b7cfb5af51aac8733161db4bc64845267ca0"
+    );
+    assert_eq!(image_hash(&image(255), &encoding), hash);
+    assert_ne!(image_hash(&image(0), &encoding), hash);
+    // A different encoding uploads a different image
+    let encoding = ImageEncoding {
+        quality: 80,
+        ..ImageEncoding::default()
+    };
+    assert_ne!(image_hash(&image(255), &encoding), hash);
+}
+
+#[derive(Parser, Debug)]
+struct Cli {
+    #[command(flatten)]
+    display: DisplayArgs,
+}
+
+#[test]
+fn skips_the_upload_if_only_the_time_of_the_update_changed() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/imgupload");
+        then.status(200);
+    });
+    let cli = Cli::try_parse_from([
+        "elstatus",
+        "--ap-address",
+        &server.address().to_string(),
+        "--main-tag",
+        "0000021EDEADBEEF",
+        "--secondary-tag",
+        "0000021EDEADBEEE",
+    ])
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = DataDir::new(Some(dir.path().to_owned()));
+    let broken: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let renderer = cli.display.renderer();
+    let run_at = |hour| RunContext {
+        run_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+        started_at: Local.with_ymd_and_hms(2024, 5, 17, hour, 30, 0).unwrap(),
+    };
+
+    update_since(
+        &broken,
+        None,
+        &cli.display,
+        &renderer,
+        &run_at(8),
+        &data_dir,
+    )
+    .unwrap();
+    mock.assert_hits(2);
+    // Without the previous state, the images are compared
+    update_since(
+        &broken,
+        None,
+        &cli.display,
+        &renderer,
+        &run_at(9),
+        &data_dir,
+    )
+    .unwrap();
+    mock.assert_hits(2);
+}
+
+#[test]
+fn keeps_the_images_the_displays_were_updated_with() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/imgupload");
+        then.status(200);
+    });
+    let cli = Cli::try_parse_from([
+        "elstatus",
+        "--ap-address",
+        &server.address().to_string(),
+        "--main-tag",
+        "0000021EDEADBEEF",
+        "--secondary-tag",
+        "0000021EDEADBEEE",
+        "--qr-code",
+    ])
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = DataDir::new(Some(dir.path().to_owned()));
+    let broken: Vec<Equipment> = serde_json::from_value(json!([
+        {
+            "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee",
+            "coordinates": [13.18103, 52.42221],
+        },
+    ]))
+    .unwrap();
+    let renderer = cli.display.renderer();
+    let run = RunContext {
+        run_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+        started_at: Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap(),
+    };
+    let updated = UpdatedImages::default();
+    let notifier = DisplayNotifier {
+        args: &cli.display,
+        previous: Some(&broken),
+        updated: &updated,
+        renderer: &renderer,
+        run: &run,
+        data_dir: &data_dir,
+    };
+    let summary = StatusSummary::new(&broken, &[]);
+
+    // Nothing changed, so nothing was rendered
+    notifier.notify(&broken, &[], &summary).unwrap();
+    assert!(updated.borrow().is_none());
+
+    let notifier = DisplayNotifier {
+        previous: None,
+        ..notifier
+    };
+    notifier.notify(&broken, &[], &summary).unwrap();
+    assert_eq!(
+        updated.borrow().as_ref(),
+        Some(&renderer.render(&broken, &run.started_at))
+    );
+}
+
+#[test]
+fn upload_path_must_be_a_path() {
+    let args = |path| {
+        Cli::try_parse_from([
+            "elstatus",
+            "--ap-address",
+            "192.168.1.2",
+            "--main-tag",
+            "0000021EDEADBEEF",
+            "--secondary-tag",
+            "0000021EDEADBEEE",
+            "--upload-path",
+            path,
+        ])
+    };
+
+    assert!(args("/upload_image").is_ok());
+    assert!(args("upload_image").is_err());
+    assert!(args("http://192.168.1.2/imgupload").is_err());
+}
+
+#[test]
+fn normalizes_tag_macs() {
+    for mac in [
+        "00:00:02:1C:4B:3F:3B:12",
+        "00-00-02-1c-4b-3f-3b-12",
+        "0000021c4B3F3B12",
+    ] {
+        assert_eq!(parse_mac(mac).unwrap(), "0000021C4B3F3B12");
+    }
+    assert_eq!(parse_mac("AA:BB:CC:DD:EE:FF").unwrap(), "AABBCCDDEEFF");
+
+    for mac in [
+        "",
+        "00:00:02:1C:4B:3F:3B",
+        "00:00:02:1C:4B:3F:3B:1",
+        "0:00:02:1C:4B:3F:3B:12",
+        "0000021C4B3F3B1",
+        "0000021C4B3F3B1G",
+        "00:00:02:1C:4B:3F:3B:12:34",
+        "Ä0:00:02:1C:4B:3F",
+    ] {
+        assert!(parse_mac(mac).is_err(), "{mac}");
+    }
+}
+
+#[test]
+fn rejects_invalid_tag_macs_when_parsing_the_arguments() {
+    let err = Cli::try_parse_from([
+        "elstatus",
+        "--ap-address",
+        "192.168.1.2",
+        "--main-tag",
+        "0000021EDEADBEEF",
+        "--secondary-tag",
+        "0000021EDEADBEE",
+    ])
+    .unwrap_err();
+
+    assert!(err.to_string().contains("0000021EDEADBEE"), "{err}");
+}
+
+#[test]
+fn reduces_the_jpeg_quality_to_fit_the_size_limit() {
+    // Noise doesn't compress well, so the quality makes a difference
+    let image = RgbImage::from_fn(296, 128, |x, y| {
+        let gray = ((x * 7919 + y * 104729) % 251) as u8;
+        Rgb([gray, gray, gray])
+    });
+    let full = ImageEncoding::default().encode(&image, "noise").unwrap();
+
+    let limit = full.len() / 2;
+    let warned = ImageEncoding {
+        max_size: Some(limit),
+        ..ImageEncoding::default()
+    };
+    assert_eq!(warned.encode(&image, "noise").unwrap(), full);
+
+    let reduced = ImageEncoding {
+        reduce_quality: true,
+        ..warned
+    };
+    let jpeg = reduced.encode(&image, "noise").unwrap();
+    assert!(jpeg.len() <= limit, "{} > {}", jpeg.len(), limit);
+    assert!(image::load_from_memory(&jpeg).is_ok());
+}
+
+#[test]
+fn rejects_jpeg_qualities_outside_1_to_100() {
+    let args = |quality: &str| {
+        Cli::try_parse_from([
+            "elstatus",
+            "--ap-address",
+            "192.168.1.2",
+            "--main-tag",
+            "0000021EDEADBEEF",
+            "--secondary-tag",
+            "0000021EDEADBEE0",
+            "--jpeg-quality",
+            quality,
+        ])
+    };
+
+    assert!(args("0").is_err());
+    assert!(args("101").is_err());
+    assert!(args("80").is_ok());
+}
+
+#[test]
+fn reduces_images_to_black_and_white() {
+    let image = RgbImage::from_fn(4, 1, |x, _| {
+        Rgb(match x {
+            0 => [255, 0, 0],
+            1 => [100, 100, 100],
+            2 => [200, 200, 200],
+            _ => [255, 255, 255],
+        })
+    });
+
+    let monochrome = to_monochrome(&image, Monochrome::Threshold, 128);
+    let pixels: Vec<_> = monochrome.pixels().map(|pixel| pixel.0).collect();
+    assert_eq!(pixels, [[0; 3], [0; 3], [255; 3], [255; 3]]);
+
+    let lighter = to_monochrome(&image, Monochrome::Threshold, 90);
+    assert_eq!(lighter.get_pixel(1, 0).0, [255; 3]);
+
+    assert_eq!(to_monochrome(&image, Monochrome::Off, 128), image);
+}
+
+#[test]
+fn dithers_gray_areas_into_a_pattern() {
+    let gray = RgbImage::from_pixel(20, 20, Rgb([128, 128, 128]));
+
+    let dithered = to_monochrome(&gray, Monochrome::FloydSteinberg, 128);
+
+    assert!(dithered
+        .pixels()
+        .all(|pixel| pixel.0 == [0; 3] || pixel.0 == [255; 3]));
+    let black = dithered.pixels().filter(|pixel| pixel.0 == [0; 3]).count();
+    assert!(
+        (180..=220).contains(&black),
+        "{black} of 400 pixels are black"
+    );
+}
+
+#[test]
+fn writes_both_images_into_the_given_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let image = RgbImage::from_pixel(296, 128, Rgb([255, 255, 255]));
+
+    let (main, secondary) = write_images(
+        dir.path(),
+        &(image.clone(), image),
+        &ImageEncoding::default(),
+    )
+    .unwrap();
+
+    assert_eq!(main, dir.path().join("elstatus.jpg"));
+    assert_eq!(secondary, dir.path().join("elstatus_secondary.jpg"));
+    assert!(main.is_file() && secondary.is_file());
+}
+
+#[test]
+fn renders_repeatedly_with_the_same_renderer() {
+    let broken: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let working: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let updated_at = Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap();
+
+    let renderer = DisplayRenderer::new();
+    let first = renderer.render(&broken, &updated_at);
+    let unchanged = renderer.render(&broken, &updated_at);
+    let fixed = renderer.render(&working, &updated_at);
+    let again = renderer.render(&broken, &updated_at);
+
+    // Rendering the same status again draws the whole image again, not just what changed
+    assert_eq!(first, unchanged);
+    assert_eq!(first, again);
+    assert_ne!(first.0, fixed.0);
+    assert_eq!(first, DisplayRenderer::new().render(&broken, &updated_at));
+}
+
+#[test]
+fn shows_that_all_elevators_work() {
+    let working: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let updated_at = Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap();
+    let renderer = DisplayRenderer::new();
+
+    // Without any elevator, nothing is listed either, but that's not all clear
+    let (_, all_clear) = renderer.render(&working, &updated_at);
+    let (_, nothing) = renderer.render(&[], &updated_at);
+    assert_ne!(all_clear, nothing);
+}
+
+#[test]
+fn forgets_what_the_displays_show() {
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = DataDir::new(Some(dir.path().to_owned()));
+    std::fs::write(dir.path().join("elstatus.state.json"), "[]").unwrap();
+    std::fs::write(dir.path().join("elstatus.uploads.json"), "{}").unwrap();
+    assert_eq!(load_state(&data_dir), Some(vec![]));
+
+    forget_state(&data_dir).unwrap();
+
+    assert_eq!(load_state(&data_dir), None);
+    assert!(!dir.path().join("elstatus.uploads.json").exists());
+    // Nothing left to forget
+    forget_state(&data_dir).unwrap();
+}
+
+#[test]
+fn shows_unknown_elevators_as_broken_unless_counted_as_working() {
+    let equipment = |working| Equipment::new("Gleis 1/2", "elevator", working, None);
+    let maintenance = equipment(Some(false)).with_maintenance(true);
+
+    assert!(shown_as_broken(&equipment(Some(false)), None));
+    assert!(shown_as_broken(&maintenance, None));
+    assert!(!shown_as_broken(&equipment(Some(true)), None));
+    assert!(shown_as_broken(&equipment(None), None));
+    assert!(shown_as_broken(&equipment(None), Some(UnknownAs::Broken)));
+    assert!(!shown_as_broken(&equipment(None), Some(UnknownAs::Working)));
+}
@@ -0,0 +1,312 @@
+use chrono::{Local, TimeZone};
+use elstatus::{
+    change::status_changes,
+    email::{
+        default_templates, errors_context, has_issues, status_context, structured_errors_json,
+        subject, SmtpRelay,
+    },
+    run::RunContext,
+    summary::StatusSummary,
+    Equipment, EquipmentAccessError, EquipmentList,
+};
+use serde_json::json;
+use std::error::Error;
+
+fn equipments() -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Ausgang Vorplatz", "category": "elevator", "working": null, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 1", "category": "elevator", "working": true, "place": "Potsdam Hbf" },
+    ]))
+    .unwrap()
+}
+
+fn errors() -> Vec<Box<dyn Error>> {
+    vec!["Could not find elevator: Hauptbahnhof Nord".into()]
+}
+
+fn render(template: &str, context: &tera::Context) -> String {
+    default_templates()
+        .unwrap()
+        .render(template, context)
+        .unwrap()
+}
+
+#[test]
+fn status_templates() {
+    let (equipments, errors) = (equipments(), errors());
+    let summary = StatusSummary::new(&equipments, &errors);
+    let context = status_context(&equipments, &errors, &summary, &[], None, None, false);
+
+    insta::assert_snapshot!("status_html", render("status.html", &context));
+    insta::assert_snapshot!("status_txt", render("status.txt", &context));
+}
+
+#[test]
+fn status_templates_with_image() {
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+    let context = status_context(&equipments, &[], &summary, &[], None, None, true);
+
+    insta::assert_snapshot!("status_html_with_image", render("status.html", &context));
+}
+
+#[test]
+fn all_clear_status_templates() {
+    let equipments: Vec<_> = equipments()
+        .into_iter()
+        .filter(|equipment| equipment.to_string().starts_with('✅'))
+        .collect();
+    let summary = StatusSummary::new(&equipments, &[]);
+    let context = status_context(&equipments, &[], &summary, &[], None, None, false);
+
+    assert_eq!(summary.headline(), "✅ Alle Aufzüge funktionieren!");
+    insta::assert_snapshot!("all_clear_html", render("status.html", &context));
+    insta::assert_snapshot!("all_clear_txt", render("status.txt", &context));
+}
+
+#[test]
+fn status_templates_list_the_changes() {
+    let equipments = equipments();
+    // Gleis 1/2 was broken and Gleis 3/4 worked
+    let previous: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let changes = status_changes(&previous, &equipments);
+    let summary = StatusSummary::new(&equipments, &[]);
+    let context = status_context(&equipments, &[], &summary, &changes, None, None, false);
+
+    insta::assert_snapshot!("status_html_changes", render("status.html", &context));
+    insta::assert_snapshot!("status_txt_changes", render("status.txt", &context));
+}
+
+#[test]
+fn status_templates_name_the_run() {
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+    let run = RunContext {
+        run_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+        started_at: Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap(),
+    };
+    let context = status_context(&equipments, &[], &summary, &[], None, Some(&run), false);
+
+    for template in ["status.html", "status.txt"] {
+        let message = render(template, &context);
+        assert!(
+            message.contains(
+                "Lauf 67e55044-10b1-426f-9247-bb680e5fe0c8, gestartet am 17.05.2024 um 08:30:00"
+            ),
+            "{template}: {message}"
+        );
+    }
+    assert!(render("errors.txt", &errors_context(&errors(), Some(&run)))
+        .contains("Run 67e55044-10b1-426f-9247-bb680e5fe0c8, started 2024-05-17 08:30:00"));
+}
+
+#[test]
+fn recently_broken_elevators_are_listed_without_alerting() {
+    let equipments: Vec<_> = equipments()
+        .into_iter()
+        .map(|equipment| {
+            let recently_broken = equipment.working() == Some(false);
+            equipment.with_recently_broken(recently_broken)
+        })
+        .collect();
+    // Gleis 3/4 worked in the last status email
+    let previous: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let changes = status_changes(&previous, &equipments);
+    let summary = StatusSummary::new(&equipments, &[]);
+    let context = status_context(&equipments, &[], &summary, &changes, None, None, false);
+
+    assert!(changes.is_empty(), "{changes:?}");
+    assert_eq!(
+        subject(&summary, &changes),
+        "🕒✅❔ Aufzug kürzlich als defekt gemeldet"
+    );
+    for template in ["status.html", "status.txt"] {
+        let message = render(template, &context);
+        assert!(!message.contains("Außer Betrieb"), "{template}: {message}");
+        assert!(!message.contains("⛔"), "{template}: {message}");
+    }
+    assert!(render("status.txt", &context).contains("Status: Kürzlich als defekt gemeldet"));
+    assert!(render("status.html", &context).contains("🕒"));
+}
+
+#[test]
+fn status_templates_count_the_broken_elevators_left_out() {
+    let mut equipments = equipments();
+    equipments.push(
+        serde_json::from_value(json!(
+            { "name": "Gleis 2", "category": "elevator", "working": false, "place": "Alexanderplatz" }
+        ))
+        .unwrap(),
+    );
+    let summary = StatusSummary::new(&equipments, &[]);
+    let context = status_context(&equipments, &[], &summary, &[], Some(1), None, false);
+
+    assert_eq!(summary.num_failed, 2);
+    for template in ["status.html", "status.txt"] {
+        let message = render(template, &context);
+        assert!(message.contains("Gleis 2"), "{template}: {message}");
+        assert!(!message.contains("Gleis 3/4"), "{template}: {message}");
+        assert!(
+            message.contains("…und 1 weitere außer Betrieb"),
+            "{template}: {message}"
+        );
+    }
+}
+
+#[test]
+fn subject_leads_with_the_net_change() {
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+    let previous = |working: [bool; 2]| -> Vec<Equipment> {
+        serde_json::from_value(json!([
+            { "name": "Gleis 1/2", "category": "elevator", "working": working[0], "place": "Berlin-Wannsee" },
+            { "name": "Gleis 3/4", "category": "elevator", "working": working[1], "place": "Berlin-Wannsee" },
+        ]))
+        .unwrap()
+    };
+    let subject_since =
+        |working| subject(&summary, &status_changes(&previous(working), &equipments));
+
+    assert_eq!(subject_since([true, false]), summary.headline());
+    assert_eq!(
+        subject_since([false, false]),
+        format!("🎉 1 wieder in Betrieb - {}", summary.headline())
+    );
+    assert_eq!(
+        subject_since([true, true]),
+        format!("📉 1 neu defekt - {}", summary.headline())
+    );
+    assert_eq!(
+        subject_since([false, true]),
+        format!(
+            "🔀 1 neu defekt, 1 wieder in Betrieb - {}",
+            summary.headline()
+        )
+    );
+}
+
+#[test]
+fn errors_template() {
+    insta::assert_snapshot!(
+        "errors_txt",
+        render("errors.txt", &errors_context(&errors(), None))
+    );
+}
+
+#[test]
+fn only_unchanged_all_clear_status_has_no_issues() {
+    let all_working: Vec<_> = equipments()
+        .into_iter()
+        .filter(|equipment| equipment.working() == Some(true))
+        .collect();
+    let summary = StatusSummary::new(&all_working, &[]);
+
+    assert!(!has_issues(&summary, Some(&all_working), &all_working));
+    // The first run, or any change, is worth an email
+    assert!(has_issues(&summary, None, &all_working));
+    assert!(has_issues(&summary, Some(&all_working[..1]), &all_working));
+
+    let (equipments, errors) = (equipments(), errors());
+    let broken = StatusSummary::new(&equipments, &errors);
+    assert!(has_issues(&broken, Some(&equipments), &equipments));
+}
+
+#[test]
+fn errors_template_names_the_failed_stations() {
+    let wannsee = EquipmentList::builder()
+        .latitude(52.422207)
+        .longitude(13.181025)
+        .station_label("Berlin-Wannsee")
+        .build()
+        .unwrap();
+    let unlabeled = EquipmentList::builder()
+        .latitude(52.5)
+        .longitude(13.4)
+        .build()
+        .unwrap();
+    let errors: Vec<Box<dyn Error>> = vec![
+        Box::new(elstatus::Error::from(EquipmentAccessError::RateLimited).for_station(&wannsee)),
+        Box::new(
+            elstatus::Error::from(EquipmentAccessError::NoEquipmentFound {
+                latitude: 52.5,
+                longitude: 13.4,
+            })
+            .for_station(&unlabeled),
+        ),
+        "Could not find elevator: Hauptbahnhof Nord".into(),
+    ];
+
+    insta::assert_snapshot!(
+        "errors_txt_failed_stations",
+        render("errors.txt", &errors_context(&errors, None))
+    );
+}
+
+#[test]
+fn structured_errors_keep_the_details() {
+    let list = EquipmentList::builder()
+        .latitude(52.422207)
+        .longitude(13.181025)
+        .station_label("Berlin-Wannsee")
+        .build()
+        .unwrap();
+    let errors: Vec<Box<dyn Error>> = vec![
+        Box::new(
+            elstatus::Error::from(EquipmentAccessError::HTTPRequestError {
+                status: reqwest::StatusCode::BAD_GATEWAY,
+                response_text: "Bad Gateway".to_owned(),
+            })
+            .for_station(&list),
+        ),
+        Box::new(EquipmentAccessError::CannotFindEquipment {
+            query_text: "Hauptbahnhof Nord".to_owned(),
+        }),
+        "Could not read fixture".into(),
+    ];
+
+    insta::assert_snapshot!("errors_json", structured_errors_json(&errors).unwrap());
+}
+
+#[test]
+fn parses_smtp_relays() {
+    assert_eq!(
+        "smtp2.example.org".parse(),
+        Ok(SmtpRelay {
+            server: "smtp2.example.org".into(),
+            port: None,
+            credentials: None,
+        })
+    );
+    // The user may be an email address
+    let relay: SmtpRelay = "elstatus@example.org:p@ss@smtp2.example.org:587"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        relay,
+        SmtpRelay {
+            server: "smtp2.example.org".into(),
+            port: Some(587),
+            credentials: Some(("elstatus@example.org".into(), "p@ss".into())),
+        }
+    );
+    // Without the credentials
+    assert_eq!(relay.to_string(), "smtp2.example.org:587");
+
+    assert!("elstatus@smtp2.example.org"
+        .parse::<SmtpRelay>()
+        .unwrap_err()
+        .contains("Missing password"));
+    assert!("smtp2.example.org:smtp"
+        .parse::<SmtpRelay>()
+        .unwrap_err()
+        .contains("Invalid port smtp"));
+}
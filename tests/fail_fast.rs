@@ -0,0 +1,50 @@
+use httpmock::{Method::POST, MockServer};
+use std::process::{Command, Output};
+
+fn webhook(server: &MockServer, args: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--data-dir", dir.path().to_str().unwrap()])
+        .args(args)
+        .args(["webhook", "--url", &server.url("/hook")])
+        .current_dir(dir.path())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn passes_the_errors_on_by_default() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .body_contains("Could not read fixture");
+        then.status(204);
+    });
+
+    let output = webhook(&server, &["--fixture", "missing.json"]);
+
+    mock.assert();
+    // Nothing could be checked
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn stops_at_the_first_error_with_fail_fast() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/hook");
+        then.status(204);
+    });
+
+    let output = webhook(&server, &["--fixture", "missing.json", "--fail-fast"]);
+
+    mock.assert_hits(0);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Could not read fixture missing.json"),
+        "{stderr}"
+    );
+    // Not 1, which would mean that an elevator is broken
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,86 @@
+use httpmock::{Method::POST, MockServer};
+use std::process::Command;
+
+#[test]
+fn runs_the_pipeline_on_a_fixture_without_the_api() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .json_body_partial(r#"{"summary": {"num_ok": 3, "num_failed": 2}}"#)
+            .body_contains("Potsdam Griebnitzsee");
+        then.status(204);
+    });
+
+    let status = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--fixture", "examples/elstatus.broken.json"])
+        .args(["webhook", "--url", &server.url("/hook")])
+        // Make sure the API is never asked
+        .env_remove("WHEELMAP_TOKEN")
+        .env_remove("WHEELMAP_TOKEN_FILE")
+        .status()
+        .unwrap();
+
+    mock.assert();
+    // Some of the elevators in the fixture are broken
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn reports_a_missing_fixture() {
+    let output = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--fixture", "does-not-exist.json", "render-only"])
+        // render-only still writes the (empty) images
+        .current_dir(std::env::temp_dir())
+        .output()
+        .unwrap();
+
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("Could not read fixture does-not-exist.json"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn keeps_generated_files_in_the_data_dir() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let data_dir = temp_dir.path().join("elstatus");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--fixture", "examples/elstatus.broken.json"])
+        .arg("--data-dir")
+        .arg(&data_dir)
+        // Keeps a status history
+        .args(["--min-status-age", "10m", "render-only"])
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(1));
+    for file in [
+        "elstatus.jpg",
+        "elstatus_secondary.jpg",
+        "elstatus.history.json",
+    ] {
+        assert!(data_dir.join(file).exists(), "{} is missing", file);
+    }
+}
+
+#[test]
+fn render_only_takes_the_options_of_the_display() {
+    let render = |args: &[&str]| {
+        let data_dir = tempfile::tempdir().unwrap();
+        let status = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+            .args(["--fixture", "examples/elstatus.broken.json"])
+            .arg("--data-dir")
+            .arg(data_dir.path())
+            .arg("render-only")
+            .args(args)
+            .status()
+            .unwrap();
+        assert_eq!(status.code(), Some(1));
+        std::fs::read(data_dir.path().join("elstatus_secondary.jpg")).unwrap()
+    };
+
+    let default = render(&[]);
+    assert!(render(&["--jpeg-quality", "10"]).len() < default.len());
+    assert_ne!(render(&["--large-text", "--date-format", "%H:%M"]), default);
+}
@@ -0,0 +1,36 @@
+use elstatus::{to_geojson, Equipment};
+use serde_json::json;
+
+#[test]
+fn emits_a_point_feature_per_equipment_with_coordinates() {
+    let equipments = [
+        Equipment::new(
+            "Gleis 1/2",
+            "elevator",
+            Some(false),
+            Some("Berlin-Wannsee".into()),
+        )
+        .with_coordinates([13.181025, 52.422207]),
+        Equipment::new("Gleis 3/4", "elevator", None, None),
+    ];
+
+    assert_eq!(
+        to_geojson(&equipments),
+        json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [13.181025, 52.422207] },
+                "properties": { "name": "Gleis 1/2", "place": "Berlin-Wannsee", "working": false },
+            }],
+        })
+    );
+}
+
+#[test]
+fn empty_list_is_an_empty_collection() {
+    assert_eq!(
+        to_geojson(&[]),
+        json!({ "type": "FeatureCollection", "features": [] })
+    );
+}
@@ -0,0 +1,87 @@
+use clap::Parser;
+use elstatus::{matrix::MatrixArgs, summary::StatusSummary, Equipment};
+use httpmock::{Method::PUT, MockServer};
+use serde_json::json;
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    matrix: MatrixArgs,
+}
+
+#[test]
+fn sends_broken_elevators_to_the_room() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(PUT)
+            .path_matches(
+                httpmock::Regex::new(
+                    r"^/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/\d+$",
+                )
+                .unwrap(),
+            )
+            .header("Authorization", "Bearer secret")
+            .json_body_partial(r#"{"msgtype": "m.text", "format": "org.matrix.custom.html"}"#)
+            .body_contains("Aufzug Gleis 1/2 (Berlin-Wannsee)")
+            .body_contains("<li>⛔ Aufzug Gleis 1/2 (Berlin-Wannsee)</li>");
+        then.status(200).json_body(json!({ "event_id": "$event" }));
+    });
+
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap();
+    let homeserver_url = server.base_url();
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--homeserver-url",
+        &homeserver_url,
+        "--access-token",
+        "secret",
+        "--room-id",
+        "!room:example.org",
+    ]);
+
+    let summary = StatusSummary::new(&equipments, &[]);
+    elstatus::matrix::send_result(&equipments, &summary, None, &cli.matrix).unwrap();
+
+    mock.assert();
+}
+
+#[test]
+fn counts_the_broken_elevators_left_out() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(PUT)
+            .body_contains("Aufzug Gleis 1/2 (Berlin-Wannsee)")
+            .body_contains("…und 1 weitere außer Betrieb")
+            .matches(|request| {
+                !String::from_utf8_lossy(request.body.as_deref().unwrap_or_default())
+                    .contains("Gleis 3/4")
+            });
+        then.status(200).json_body(json!({ "event_id": "$event" }));
+    });
+
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Potsdam Hbf" },
+    ]))
+    .unwrap();
+    let homeserver_url = server.base_url();
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--homeserver-url",
+        &homeserver_url,
+        "--access-token",
+        "secret",
+        "--room-id",
+        "!room:example.org",
+    ]);
+
+    let summary = StatusSummary::new(&equipments, &[]);
+    assert!(summary.headline().contains('⛔'));
+    elstatus::matrix::send_result(&equipments, &summary, Some(1), &cli.matrix).unwrap();
+
+    mock.assert();
+}
@@ -0,0 +1,93 @@
+use elstatus::{merge_equipment, Equipment};
+use serde_json::json;
+
+fn equipment(id: &str, working: Option<bool>, last_update: Option<&str>) -> Equipment {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": "Gleis 1/2",
+        "category": "elevator",
+        "working": working,
+        "place": "Berlin-Wannsee",
+        "last_update": last_update,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn known_status_wins_over_unknown() {
+    let primary = equipment("a", None, Some("2024-01-05T12:00:00Z"));
+    let secondary = equipment("b", Some(false), None);
+
+    assert_eq!(
+        merge_equipment(vec![vec![primary], vec![secondary.clone()]]),
+        vec![secondary]
+    );
+}
+
+#[test]
+fn most_recent_update_wins() {
+    let older = equipment("a", Some(true), Some("2024-01-05T12:00:00Z"));
+    let newer = equipment("b", Some(false), Some("2024-01-05T13:00:00Z"));
+    let undated = equipment("c", Some(true), None);
+
+    assert_eq!(
+        merge_equipment(vec![
+            vec![undated.clone()],
+            vec![older.clone()],
+            vec![newer.clone()]
+        ]),
+        vec![newer.clone()]
+    );
+    assert_eq!(
+        merge_equipment(vec![vec![newer.clone()], vec![older]]),
+        vec![newer]
+    );
+}
+
+#[test]
+fn earlier_source_wins_ties() {
+    let primary = equipment("a", Some(true), None);
+    let secondary = equipment("b", Some(false), None);
+
+    assert_eq!(
+        merge_equipment(vec![vec![primary.clone()], vec![secondary]]),
+        vec![primary]
+    );
+}
+
+#[test]
+fn keeps_different_elevators_apart() {
+    let gleis_1 = equipment("a", Some(true), None);
+    let gleis_3: Equipment = serde_json::from_value(json!({
+        "id": "b",
+        "name": "Gleis 3/4",
+        "category": "elevator",
+        "working": false,
+        "place": "Berlin-Wannsee",
+    }))
+    .unwrap();
+
+    assert_eq!(
+        merge_equipment(vec![vec![gleis_1.clone()], vec![gleis_3.clone()]]),
+        vec![gleis_1, gleis_3]
+    );
+}
+
+#[test]
+fn same_id_is_the_same_elevator() {
+    let primary = equipment("a", Some(true), None);
+    let renamed: Equipment = serde_json::from_value(json!({
+        "id": "a",
+        "name": "zu Gleis 1/2 (S-Bahn)",
+        "category": "elevator",
+        "working": false,
+        "place": "Berlin-Wannsee",
+        "last_update": "2024-01-05T12:00:00Z",
+    }))
+    .unwrap();
+
+    assert_eq!(
+        merge_equipment(vec![vec![primary], vec![renamed.clone()]]),
+        vec![renamed]
+    );
+}
@@ -0,0 +1,83 @@
+use elstatus::{
+    data_dir::DataDir,
+    metrics::{score_bucket, MatchMetrics},
+    Equipment, EquipmentAccessError, EquipmentList, EquipmentResults, Error,
+};
+
+fn list(station_label: &str) -> EquipmentList {
+    EquipmentList::builder()
+        .latitude(52.422207)
+        .longitude(13.181025)
+        .station_label(station_label)
+        .add_search("Gleis 1/2")
+        .build()
+        .unwrap()
+}
+
+fn results(scores: &[f32], unmatched: &[&str]) -> Result<EquipmentResults, Error> {
+    Ok(EquipmentResults {
+        equipments: scores
+            .iter()
+            .map(|_| Equipment::new("Gleis 1/2", "elevator", Some(true), None))
+            .collect(),
+        errors: unmatched
+            .iter()
+            .map(|query| EquipmentAccessError::CannotFindEquipment {
+                query_text: query.to_string(),
+            })
+            .collect(),
+        scores: scores.to_vec(),
+    })
+}
+
+#[test]
+fn counts_scores_including_their_upper_bound() {
+    assert_eq!(score_bucket(0.0), 0);
+    assert_eq!(score_bucket(0.1), 0);
+    assert_eq!(score_bucket(0.15), 1);
+    assert_eq!(score_bucket(0.9), 8);
+    assert_eq!(score_bucket(1.0), 9);
+}
+
+#[test]
+fn accumulates_the_match_quality_across_runs() {
+    let lists = [list("Wannsee"), list("Zoo"), list("Potsdam Hbf")];
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = DataDir::new(Some(dir.path().to_owned()));
+
+    for _ in 0..2 {
+        let mut metrics = MatchMetrics::load(&data_dir);
+        metrics.record(
+            &lists,
+            &[
+                results(&[1.0, 0.75], &["Hauptbahnhof Nord"]),
+                results(&[0.5], &[]),
+                // Lists that couldn't be fetched don't count as unmatched
+                Err(Error::IncompleteEquipmentList("latitude")),
+            ],
+        );
+        metrics.store(&data_dir).unwrap();
+    }
+
+    let metrics = MatchMetrics::load(&data_dir);
+    assert_eq!(metrics.runs, 2);
+    assert_eq!(metrics.num_scores(), 6);
+    assert_eq!(metrics.score_buckets[9], 2);
+    assert_eq!(metrics.score_buckets[7], 2);
+    assert_eq!(metrics.score_buckets[4], 2);
+    assert_eq!(metrics.unmatched.len(), 1);
+    assert_eq!(metrics.unmatched["Wannsee"], 2);
+    assert_eq!(
+        metrics.summary(),
+        "📊 Match scores of 6 searches in 2 runs (mean 0.750): ≤0.5: 2, ≤0.8: 2, ≤1.0: 2\n\
+         📊 Unmatched searches by station: Wannsee: 2"
+    );
+
+    let prometheus = std::fs::read_to_string(dir.path().join("elstatus.metrics.prom")).unwrap();
+    assert!(prometheus.contains("elstatus_match_score_bucket{le=\"0.5\"} 2\n"));
+    assert!(prometheus.contains("elstatus_match_score_bucket{le=\"0.9\"} 4\n"));
+    assert!(prometheus.contains("elstatus_match_score_bucket{le=\"+Inf\"} 6\n"));
+    assert!(prometheus.contains("elstatus_match_score_sum 4.5\n"));
+    assert!(prometheus.contains("elstatus_match_score_count 6\n"));
+    assert!(prometheus.contains("elstatus_unmatched_searches_total{station=\"Wannsee\"} 2\n"));
+}
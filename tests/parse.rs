@@ -0,0 +1,275 @@
+use elstatus::{parse_equipment, parse_equipment_in, Equipment, EquipmentAccessError};
+use serde_json::{json, Value};
+
+fn equipment(name: &str, category: &str, working: Option<bool>, place: Option<&str>) -> Equipment {
+    Equipment::new(name, category, working, place.map(str::to_owned))
+}
+
+fn parse_properties(properties: Value) -> Equipment {
+    parse_equipment(&json!({ "properties": properties })).unwrap()
+}
+
+#[test]
+fn parses_all_properties() {
+    let parsed = parse_properties(json!({
+        "category": "elevator",
+        "isWorking": true,
+        "description": { "de": "zu Gleis 1/2", "en": "to platform 1/2" },
+        "placeInfoName": "Berlin-Wannsee",
+    }));
+
+    assert_eq!(
+        parsed,
+        equipment(
+            "zu Gleis 1/2",
+            "elevator",
+            Some(true),
+            Some("Berlin-Wannsee")
+        )
+    );
+    assert_eq!(parsed.name(), "zu Gleis 1/2");
+    assert_eq!(parsed.category(), "elevator");
+    assert_eq!(parsed.working(), Some(true));
+    assert_eq!(parsed.place(), Some("Berlin-Wannsee"));
+}
+
+#[test]
+fn falls_back_through_the_preferred_languages() {
+    let feature = json!({ "properties": {
+        "description": { "de": "zu Gleis 1/2", "en": "to platform 1/2" },
+    }});
+    let name = |languages: &[&str]| {
+        let languages: Vec<String> = languages.iter().map(|l| l.to_string()).collect();
+        parse_equipment_in(&feature, &languages)
+            .unwrap()
+            .name()
+            .to_owned()
+    };
+
+    assert_eq!(name(&["en", "de"]), "to platform 1/2");
+    assert_eq!(name(&["fr", "de"]), "zu Gleis 1/2");
+    assert_eq!(name(&[]), "zu Gleis 1/2");
+    assert_eq!(name(&["fr"]), "Cannot find description!");
+}
+
+#[test]
+fn accepts_plain_string_descriptions() {
+    let parsed = parse_properties(json!({
+        "category": "elevator",
+        "isWorking": false,
+        "description": "Ausgang Vorplatz",
+        "placeInfoName": "Berlin-Wannsee",
+    }));
+
+    assert_eq!(
+        parsed,
+        equipment(
+            "Ausgang Vorplatz",
+            "elevator",
+            Some(false),
+            Some("Berlin-Wannsee")
+        )
+    );
+}
+
+#[test]
+fn missing_description_uses_placeholder() {
+    let parsed = parse_properties(json!({ "category": "elevator", "isWorking": true }));
+
+    assert_eq!(
+        parsed,
+        equipment("Cannot find description!", "elevator", Some(true), None)
+    );
+}
+
+#[test]
+fn description_without_german_text_uses_placeholder() {
+    let parsed = parse_properties(json!({ "description": { "en": "to platform 1/2" } }));
+
+    assert_eq!(
+        parsed,
+        equipment("Cannot find description!", "elevator", None, None)
+    );
+}
+
+#[test]
+fn missing_category_defaults_to_elevator() {
+    let parsed = parse_properties(json!({ "description": "Gleis 5", "isWorking": true }));
+
+    assert_eq!(parsed, equipment("Gleis 5", "elevator", Some(true), None));
+}
+
+#[test]
+fn keeps_other_categories() {
+    let parsed = parse_properties(json!({ "description": "Gleis 5", "category": "escalator" }));
+
+    assert_eq!(parsed, equipment("Gleis 5", "escalator", None, None));
+}
+
+#[test]
+fn missing_working_status_is_unknown() {
+    let parsed = parse_properties(json!({ "description": "Gleis 5" }));
+
+    assert_eq!(parsed, equipment("Gleis 5", "elevator", None, None));
+}
+
+fn assert_working(working: Value, expected: Option<bool>) {
+    assert_eq!(
+        parse_properties(json!({ "description": "Gleis 5", "isWorking": working })),
+        equipment("Gleis 5", "elevator", expected, None),
+        "isWorking: {working}"
+    );
+}
+
+#[test]
+fn parses_boolean_working_status() {
+    assert_working(json!(true), Some(true));
+    assert_working(json!(false), Some(false));
+}
+
+#[test]
+fn parses_string_working_status() {
+    assert_working(json!("true"), Some(true));
+    assert_working(json!("false"), Some(false));
+    assert_working(json!("yes"), Some(true));
+    assert_working(json!("no"), Some(false));
+    assert_working(json!("TRUE"), Some(true));
+}
+
+#[test]
+fn parses_numeric_working_status() {
+    assert_working(json!(1), Some(true));
+    assert_working(json!(0), Some(false));
+}
+
+#[test]
+fn unrecognized_working_status_is_unknown() {
+    assert_working(json!("maybe"), None);
+    assert_working(json!(2), None);
+    assert_working(json!(0.5), None);
+    assert_working(json!(null), None);
+    assert_working(json!({ "state": "ACTIVE" }), None);
+}
+
+#[test]
+fn falls_back_to_renamed_properties() {
+    let parsed = parse_properties(json!({
+        "equipmentCategory": "elevator",
+        "operating": false,
+        "name": { "de": "zu Gleis 1/2" },
+        "placeName": "Berlin-Wannsee",
+    }));
+
+    assert_eq!(
+        parsed,
+        equipment(
+            "zu Gleis 1/2",
+            "elevator",
+            Some(false),
+            Some("Berlin-Wannsee")
+        )
+    );
+    assert_eq!(
+        parse_properties(json!({ "description": "Gleis 5", "isOperating": "yes" })),
+        equipment("Gleis 5", "elevator", Some(true), None)
+    );
+}
+
+#[test]
+fn prefers_the_current_property_names() {
+    let parsed = parse_properties(json!({
+        "category": "escalator",
+        "equipmentCategory": "elevator",
+        "isWorking": true,
+        "operating": false,
+        "description": "Gleis 5",
+        "name": "Gleis 6",
+        "placeInfoName": "Berlin-Wannsee",
+        "placeName": "Wannsee",
+    }));
+
+    assert_eq!(
+        parsed,
+        equipment("Gleis 5", "escalator", Some(true), Some("Berlin-Wannsee"))
+    );
+}
+
+#[test]
+fn unknown_working_status_falls_back_to_renamed_property() {
+    let parsed = parse_properties(json!({
+        "description": "Gleis 5",
+        "isWorking": null,
+        "operating": true,
+    }));
+
+    assert_eq!(parsed, equipment("Gleis 5", "elevator", Some(true), None));
+}
+
+#[test]
+fn parses_the_maintenance_flag() {
+    let parsed = parse_properties(json!({
+        "description": "Gleis 5",
+        "isWorking": false,
+        "isUnderMaintenance": true,
+    }));
+
+    assert!(parsed.in_maintenance());
+    assert_eq!(
+        parsed,
+        equipment("Gleis 5", "elevator", Some(false), None).with_maintenance(true)
+    );
+    assert_eq!(parsed.to_string(), "🔧 Gleis 5");
+    assert!(!parse_properties(json!({ "description": "Gleis 5" })).in_maintenance());
+}
+
+#[test]
+fn missing_properties_is_an_error() {
+    let err = parse_equipment(&json!({ "_id": "elevator-1" })).unwrap_err();
+
+    assert!(matches!(
+        err,
+        EquipmentAccessError::MissingValue(value, _) if value == "description"
+    ));
+}
+
+#[test]
+fn summarizes_equipment_in_one_line() {
+    let parsed = parse_properties(json!({
+        "isWorking": true,
+        "description": "Gleis 1/2",
+        "placeInfoName": "Wannsee",
+        "stateLastUpdate": "2024-01-05T13:37:00.000Z",
+    }));
+    assert_eq!(
+        parsed.to_string(),
+        "✅ Gleis 1/2 @ Wannsee (updated 2024-01-05)"
+    );
+
+    let unknown = equipment("Gleis 3/4", "elevator", None, None);
+    assert_eq!(unknown.to_string(), "❔ Gleis 3/4");
+}
+
+#[test]
+fn builds_equipment_by_hand() {
+    let last_update = "2024-01-05T13:37:00Z".parse().unwrap();
+    let built = Equipment::new("Gleis 1/2", "elevator", Some(false), Some("Wannsee".into()))
+        .with_id("elevator-1")
+        .with_station("Berlin-Wannsee")
+        .with_last_update(last_update)
+        .with_coordinates([13.181025, 52.422207]);
+
+    let deserialized: Equipment = serde_json::from_value(json!({
+        "id": "elevator-1",
+        "name": "Gleis 1/2",
+        "category": "elevator",
+        "working": false,
+        "place": "Wannsee",
+        "station": "Berlin-Wannsee",
+        "last_update": "2024-01-05T13:37:00Z",
+        "coordinates": [13.181025, 52.422207],
+    }))
+    .unwrap();
+    assert_eq!(built, deserialized);
+    assert_eq!(built.station(), Some("Berlin-Wannsee"));
+    assert_eq!(built.last_update(), Some(last_update));
+}
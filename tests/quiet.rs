@@ -0,0 +1,49 @@
+use chrono::NaiveTime;
+use elstatus::{quiet::QuietHoursArgs, Equipment};
+use serde_json::json;
+
+fn time(time: &str) -> NaiveTime {
+    time.parse().unwrap()
+}
+
+fn quiet_hours(start: &str, end: &str, notify_newly_broken: bool) -> QuietHoursArgs {
+    QuietHoursArgs {
+        quiet_start: Some(time(start)),
+        quiet_end: Some(time(end)),
+        notify_newly_broken,
+    }
+}
+
+fn gleis_1(working: bool) -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": working, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn quiet_hours_wrap_past_midnight() {
+    let overnight = quiet_hours("22:00", "07:00", false);
+    assert!(overnight.is_quiet(time("22:00")));
+    assert!(overnight.is_quiet(time("03:00")));
+    assert!(!overnight.is_quiet(time("07:00")));
+    assert!(!overnight.is_quiet(time("12:00")));
+
+    let lunch = quiet_hours("12:00", "13:00", false);
+    assert!(lunch.is_quiet(time("12:30")));
+    assert!(!lunch.is_quiet(time("03:00")));
+
+    assert!(!QuietHoursArgs::default().is_quiet(time("03:00")));
+}
+
+#[test]
+fn newly_broken_elevators_override_quiet_hours() {
+    let night = time("03:00");
+    let override_enabled = quiet_hours("22:00", "07:00", true);
+    assert!(override_enabled.should_notify(night, Some(&gleis_1(true)), &gleis_1(false)));
+    assert!(!override_enabled.should_notify(night, Some(&gleis_1(false)), &gleis_1(false)));
+    assert!(!override_enabled.should_notify(night, None, &gleis_1(false)));
+
+    let override_disabled = quiet_hours("22:00", "07:00", false);
+    assert!(!override_disabled.should_notify(night, Some(&gleis_1(true)), &gleis_1(false)));
+}
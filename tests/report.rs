@@ -0,0 +1,133 @@
+use chrono::{Local, TimeZone};
+use clap::Parser;
+use elstatus::{
+    notifier::Notifier,
+    report::{default_template, report_context, write_report, ReportArgs, ReportNotifier},
+    run::RunContext,
+    summary::{StatusSummary, UnknownAs},
+    Equipment,
+};
+use serde_json::json;
+use std::error::Error;
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+fn equipments() -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": true, "place": "Berlin-Wannsee",
+          "station": "Wannsee", "last_update": "2024-05-16T14:00:00Z" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": false, "place": "Berlin-Wannsee",
+          "station": "Wannsee" },
+        { "name": "Gleis 5/6", "category": "elevator", "working": false, "place": "Berlin-Wannsee",
+          "station": "Wannsee", "maintenance": true },
+        { "name": "Ausgang Vorplatz", "category": "elevator", "working": null, "place": "Potsdam Hbf" },
+    ]))
+    .unwrap()
+}
+
+fn errors() -> Vec<Box<dyn Error>> {
+    vec!["Could not find elevator: Hauptbahnhof Nord".into()]
+}
+
+fn run() -> RunContext {
+    RunContext {
+        run_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+        started_at: Local.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap(),
+    }
+}
+
+#[test]
+fn report_template() {
+    let (equipments, errors) = (equipments(), errors());
+    let summary = StatusSummary::new(&equipments, &errors);
+    let context = report_context(&equipments, &errors, &summary, &run());
+
+    insta::assert_snapshot!(
+        "report_html",
+        default_template()
+            .unwrap()
+            .render("report.html", &context)
+            .unwrap()
+    );
+}
+
+#[test]
+fn writes_the_report_with_a_custom_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let report_path = dir.path().join("report.html");
+    let template_path = dir.path().join("template.html");
+    std::fs::write(
+        &template_path,
+        "{{ headline }}{% for station, equipments in stations %}|{{ station }}: {{ equipments | length }}{% endfor %}",
+    )
+    .unwrap();
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--report-path",
+        report_path.to_str().unwrap(),
+        "--report-template",
+        template_path.to_str().unwrap(),
+    ]);
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+
+    write_report(&equipments, &[], &summary, &run(), &cli.report).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(report_path).unwrap(),
+        "⛔🔧✅❔ Achtung: Defekter Aufzug auf dem Weg!|Ungrouped: 1|Wannsee: 3"
+    );
+}
+
+#[test]
+fn reports_missing_templates_and_directories() {
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--report-path",
+        "/nonexistent/elstatus/report.html",
+        "--report-template",
+        "/nonexistent/elstatus/template.html",
+    ]);
+
+    let problems = cli.report.config_problems();
+
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert!(problems[1].contains("Directory /nonexistent/elstatus not found"));
+}
+
+#[test]
+fn notifier_writes_the_summary_of_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let report_path = dir.path().join("report.html");
+    let template_path = dir.path().join("template.html");
+    std::fs::write(&template_path, "{{ headline }}").unwrap();
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--report-path",
+        report_path.to_str().unwrap(),
+        "--report-template",
+        template_path.to_str().unwrap(),
+    ]);
+    let equipments: Vec<Equipment> = serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+        { "name": "Ausgang Vorplatz", "category": "elevator", "working": null, "place": "Potsdam Hbf" },
+    ]))
+    .unwrap();
+    let summary = StatusSummary::new(&equipments, &[]).with_unknown_as(Some(UnknownAs::Working));
+    let run = run();
+    let notifier = ReportNotifier {
+        args: &cli.report,
+        run: &run,
+    };
+
+    notifier.notify(&equipments, &[], &summary).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(report_path).unwrap(),
+        "✅ Alle Aufzüge funktionieren!"
+    );
+}
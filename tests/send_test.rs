@@ -0,0 +1,62 @@
+use httpmock::{Method::POST, MockServer};
+use std::process::{Command, Output};
+
+fn send_test(server: &MockServer, status: u16) -> (httpmock::Mock<'_>, Output) {
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .body_contains("ElStatus test notification")
+            .body_contains("Testaufzug Gleis 3/4");
+        then.status(status);
+    });
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--data-dir", dir.path().to_str().unwrap(), "--send-test"])
+        .args(["webhook", "--url", &server.url("/hook")])
+        // No equipments.json, nothing is fetched
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    (mock, output)
+}
+
+#[test]
+fn sends_fabricated_elevators() {
+    let server = MockServer::start();
+    let (mock, output) = send_test(&server, 204);
+
+    mock.assert();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ Test notification sent"), "{stdout}");
+    // Even though an elevator is broken
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+}
+
+#[test]
+fn fails_if_the_backend_does() {
+    let server = MockServer::start();
+    let (mock, output) = send_test(&server, 403);
+
+    mock.assert();
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn keeps_the_real_report_and_display_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("elstatus.state.json");
+    std::fs::write(&state, "[]").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_elstatus"))
+        .args(["--data-dir", dir.path().to_str().unwrap(), "--send-test"])
+        .args(["html", "--report-path", "report.html"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ Test notification sent"), "{stdout}");
+    assert!(!dir.path().join("report.html").exists());
+    // Nothing was uploaded, so the displays still show the real status
+    assert!(state.exists());
+}
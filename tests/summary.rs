@@ -0,0 +1,170 @@
+use elstatus::{
+    summary::{limit_broken, limit_listed, StatusSummary, UnknownAs},
+    Equipment, Priority,
+};
+
+fn summary(num_ok: usize, num_failed: usize, num_unknown: usize) -> StatusSummary {
+    StatusSummary {
+        num_ok,
+        num_failed,
+        num_critical: 0,
+        num_minor_failed: 0,
+        num_recently_broken: 0,
+        num_maintenance: 0,
+        num_unknown,
+        num_fetch_errors: 0,
+    }
+}
+
+#[test]
+fn unknown_status_can_be_counted_as_working_or_broken() {
+    let unknown = summary(2, 0, 1);
+    assert_eq!(unknown.exit_code(), 3);
+
+    let working = unknown.with_unknown_as(Some(UnknownAs::Working));
+    assert_eq!(working, summary(3, 0, 0));
+    assert_eq!(working.exit_code(), 0);
+
+    let broken = unknown.with_unknown_as(Some(UnknownAs::Broken));
+    assert_eq!(broken, summary(2, 1, 0));
+    assert_eq!(broken.exit_code(), 1);
+    assert_eq!(broken.emojis(), "⛔✅");
+
+    assert_eq!(unknown.with_unknown_as(None), unknown);
+}
+
+#[test]
+fn recently_broken_elevators_do_not_alert() {
+    let broken = |name, priority| {
+        Equipment::new(name, "elevator", Some(false), None).with_priority(priority)
+    };
+    let working = Equipment::new("Gleis 7/8", "elevator", Some(true), None);
+
+    let recently_broken = StatusSummary::new(
+        &[
+            broken("Gleis 1/2", Priority::High).with_recently_broken(true),
+            working.clone(),
+        ],
+        &[],
+    );
+    assert_eq!(recently_broken.num_failed, 0);
+    assert_eq!(recently_broken.num_critical, 0);
+    assert_eq!(recently_broken.num_recently_broken, 1);
+    assert_eq!(recently_broken.exit_code(), 0);
+    assert_eq!(recently_broken.emojis(), "🕒✅");
+
+    // A recently broken low-priority elevator doesn't hide another one that is broken for long enough
+    let broken = StatusSummary::new(
+        &[
+            broken("Gleis 1/2", Priority::Low).with_recently_broken(true),
+            broken("Gleis 3/4", Priority::Normal),
+            working,
+        ],
+        &[],
+    );
+    assert_eq!(broken.num_failed, 1);
+    assert_eq!(broken.num_minor_failed, 1);
+    assert_eq!(broken.num_recently_broken, 0);
+    assert_eq!(broken.exit_code(), 1);
+}
+
+#[test]
+fn priority_changes_the_headline() {
+    let broken = |priority| {
+        Equipment::new("Gleis 1/2", "elevator", Some(false), None).with_priority(priority)
+    };
+    let working = Equipment::new("Gleis 3/4", "elevator", Some(true), None);
+
+    let critical = StatusSummary::new(&[broken(Priority::High), working.clone()], &[]);
+    assert_eq!(
+        critical.headline(),
+        "🚨⛔✅ KRITISCH: Wichtiger Aufzug defekt!"
+    );
+    assert_eq!(critical.exit_code(), 1);
+
+    let normal = StatusSummary::new(&[broken(Priority::Normal), working.clone()], &[]);
+    assert_eq!(
+        normal.headline(),
+        "⛔✅ Achtung: Defekter Aufzug auf dem Weg!"
+    );
+
+    let minor = StatusSummary::new(&[broken(Priority::Low), working], &[]);
+    assert_eq!(
+        minor.headline(),
+        "ℹ️✅ Hinweis: Aufzug mit niedriger Priorität defekt"
+    );
+    assert_eq!(minor.num_failed, 0);
+    assert_eq!(minor.exit_code(), 0);
+}
+
+#[test]
+fn elevators_in_maintenance_are_not_broken() {
+    let maintenance =
+        |working| Equipment::new("Gleis 1/2", "elevator", working, None).with_maintenance(true);
+    let working = Equipment::new("Gleis 3/4", "elevator", Some(true), None);
+
+    let summary = StatusSummary::new(&[maintenance(Some(false)), working.clone()], &[]);
+    assert_eq!(summary.num_maintenance, 1);
+    assert_eq!(summary.num_failed, 0);
+    assert_eq!(summary.headline(), "🔧✅ Hinweis: Aufzug in Wartung");
+    assert_eq!(summary.exit_code(), 0);
+
+    // Without a working status, it is still known to be out of service
+    let summary = StatusSummary::new(&[maintenance(None)], &[]);
+    assert_eq!(summary.num_maintenance, 1);
+    assert_eq!(summary.num_unknown, 0);
+    assert_eq!(summary.exit_code(), 0);
+
+    // Maintenance that already ended doesn't count
+    let summary = StatusSummary::new(&[maintenance(Some(true)), working], &[]);
+    assert_eq!(summary.num_ok, 2);
+    assert_eq!(summary.num_maintenance, 0);
+}
+
+#[test]
+fn lists_only_the_most_important_broken_elevators() {
+    let equipment = |name: &str, working, station: &str, priority| {
+        Equipment::new(name, "elevator", working, None)
+            .with_station(station)
+            .with_priority(priority)
+    };
+    let equipments = [
+        equipment("Gleis 1", Some(false), "Wannsee", Priority::Normal),
+        equipment("Gleis 2", Some(true), "Wannsee", Priority::Normal),
+        equipment("Gleis 3", Some(false), "Wannsee", Priority::Low),
+        equipment("Gleis 4", Some(false), "Potsdam Hbf", Priority::Normal),
+        equipment("Gleis 5", Some(false), "Zoo", Priority::High),
+    ];
+    let names = |equipments: &[Equipment]| -> Vec<String> {
+        equipments
+            .iter()
+            .map(|equipment| equipment.name().to_owned())
+            .collect()
+    };
+
+    let (listed, more) = limit_broken(&equipments, Some(2));
+    assert_eq!(names(&listed), ["Gleis 2", "Gleis 4", "Gleis 5"]);
+    assert_eq!(more, 2);
+
+    let (listed, more) = limit_broken(&equipments, Some(10));
+    assert_eq!(listed, equipments);
+    assert_eq!(more, 0);
+    assert_eq!(limit_broken(&equipments, None), (equipments.to_vec(), 0));
+}
+
+#[test]
+fn limits_the_elevators_the_caller_lists() {
+    let equipment = |name: &str, working| Equipment::new(name, "elevator", working, None);
+    let equipments = [
+        equipment("Gleis 1", Some(false)),
+        equipment("Gleis 2", None),
+        equipment("Gleis 3", None),
+    ];
+
+    // The displays list the unknown ones as well, so they count towards the limit
+    let (listed, more) = limit_listed(&equipments, Some(1), |equipment| {
+        equipment.working() != Some(true)
+    });
+    assert_eq!(listed.len(), 1);
+    assert_eq!(more, 2);
+}
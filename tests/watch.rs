@@ -0,0 +1,76 @@
+use elstatus::{watch::watch_equipment, ApiClient};
+use httpmock::{Method::GET, MockServer};
+use serde_json::json;
+use std::{sync::mpsc, time::Duration};
+
+fn feature(working: Option<bool>) -> serde_json::Value {
+    json!({
+        "_id": "elevator 1",
+        "properties": {
+            "category": "elevator",
+            "isWorking": working,
+            "description": "zu Gleis 1/2 (S-Bahn)",
+            "placeInfoName": "Berlin-Wannsee",
+        }
+    })
+}
+
+fn client(server: &MockServer) -> ApiClient {
+    ApiClient::new("token".to_owned(), "elstatus-test", Duration::from_secs(5))
+        .unwrap()
+        .with_base_url(server.base_url())
+}
+
+#[test]
+fn fetches_a_single_equipment_by_its_id() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/equipment-infos/elevator%201.json")
+            .query_param("appToken", "token");
+        then.status(200).json_body(feature(Some(false)));
+    });
+
+    let equipment = client(&server).get_equipment_by_id("elevator 1").unwrap();
+
+    assert_eq!(equipment.id(), Some("elevator 1"));
+    assert_eq!(equipment.name(), "zu Gleis 1/2 (S-Bahn)");
+    assert_eq!(equipment.working(), Some(false));
+}
+
+#[test]
+fn reports_the_first_status_and_every_change() {
+    let server = MockServer::start();
+    let mut mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos/elevator%201.json");
+        then.status(200).json_body(feature(Some(true)));
+    });
+    let (changes, changed) = mpsc::channel();
+
+    let watcher = watch_equipment(
+        client(&server),
+        "elevator 1",
+        Duration::from_millis(20),
+        move |equipment| changes.send(equipment.working()).unwrap(),
+    );
+
+    let timeout = Duration::from_secs(5);
+    assert_eq!(changed.recv_timeout(timeout), Ok(Some(true)));
+    // Wait for a few more polls, which shouldn't report anything
+    while mock.hits() < 3 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(changed.try_recv().is_err());
+
+    mock.delete();
+    mock = server.mock(|when, then| {
+        when.method(GET).path("/equipment-infos/elevator%201.json");
+        then.status(200).json_body(feature(None));
+    });
+    assert_eq!(changed.recv_timeout(timeout), Ok(None));
+
+    watcher.stop();
+    let hits = mock.hits();
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(mock.hits(), hits);
+}
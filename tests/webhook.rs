@@ -0,0 +1,122 @@
+use clap::Parser;
+use elstatus::{
+    summary::{StatusSummary, UnknownAs},
+    webhook::{send_result, WebhookArgs},
+    Equipment, EquipmentAccessError,
+};
+use httpmock::{Method::POST, MockServer};
+use serde_json::json;
+use std::error::Error;
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    webhook: WebhookArgs,
+}
+
+fn equipments() -> Vec<Equipment> {
+    serde_json::from_value(json!([
+        { "name": "Gleis 1/2", "category": "elevator", "working": false, "place": "Berlin-Wannsee" },
+        { "name": "Gleis 3/4", "category": "elevator", "working": true, "place": "Berlin-Wannsee" },
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn posts_status_json_with_custom_header() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .header("Authorization", "Bearer secret")
+            .json_body_partial(
+                r#"{"summary": {"num_ok": 1, "num_failed": 1, "num_unknown": 0, "num_fetch_errors": 0}, "errors": []}"#,
+            )
+            .body_contains("Gleis 1/2");
+        then.status(204);
+    });
+
+    let url = server.url("/hook");
+    let cli = Cli::parse_from([
+        "elstatus",
+        "--url",
+        &url,
+        "--header",
+        "Authorization: Bearer secret",
+    ]);
+
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+    send_result(&equipments, &[], &summary, &cli.webhook).unwrap();
+
+    mock.assert();
+}
+
+#[test]
+fn posts_structured_errors() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/hook").json_body_partial(
+            r#"{
+                "errors": ["Could not find elevator: Gleis 5/6"],
+                "structured_errors": [{ "kind": "CannotFindEquipment", "query": "Gleis 5/6", "status": null }]
+            }"#,
+        );
+        then.status(204);
+    });
+
+    let url = server.url("/hook");
+    let cli = Cli::parse_from(["elstatus", "--url", &url]);
+    let errors: Vec<Box<dyn Error>> = vec![Box::new(EquipmentAccessError::CannotFindEquipment {
+        query_text: "Gleis 5/6".to_owned(),
+    })];
+
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &errors);
+    send_result(&equipments, &errors, &summary, &cli.webhook).unwrap();
+
+    mock.assert();
+}
+
+#[test]
+fn does_not_retry_client_errors() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/hook");
+        then.status(400);
+    });
+
+    let url = server.url("/hook");
+    let cli = Cli::parse_from(["elstatus", "--url", &url]);
+
+    let equipments = equipments();
+    let summary = StatusSummary::new(&equipments, &[]);
+    assert!(send_result(&equipments, &[], &summary, &cli.webhook).is_err());
+    mock.assert_hits(1);
+}
+
+#[test]
+fn posts_the_summary_of_the_run() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hook")
+            .json_body_partial(r#"{"summary": {"num_ok": 1, "num_failed": 2, "num_unknown": 0}}"#);
+        then.status(204);
+    });
+
+    let url = server.url("/hook");
+    let cli = Cli::parse_from(["elstatus", "--url", &url]);
+    let mut equipments = equipments();
+    equipments.push(Equipment::new(
+        "Ausgang Vorplatz",
+        "elevator",
+        None,
+        Some("Berlin-Wannsee".to_owned()),
+    ));
+    let summary = StatusSummary::new(&equipments, &[]).with_unknown_as(Some(UnknownAs::Broken));
+
+    send_result(&equipments, &[], &summary, &cli.webhook).unwrap();
+
+    mock.assert();
+}